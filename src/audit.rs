@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Display;
+
+use crate::{
+    giftexchange::ExchangePool,
+    storage::Storage,
+    vault::{self, VaultKey},
+};
+
+/// What happened to an exchange
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    /// The first draw of a pool's exchange for the year
+    Draw,
+    /// A draw that replaced an earlier one
+    Regenerate,
+    Finalize,
+    Unlock,
+    /// Pairings were shown to someone
+    Reveal,
+    /// Pairings hidden by blind organizer mode were shown to someone
+    #[serde(rename = "break-glass")]
+    BreakGlass,
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Pad so the CLI log can line actions up in a column
+        f.pad(match self {
+            AuditAction::Draw => "draw",
+            AuditAction::Regenerate => "regenerate",
+            AuditAction::Finalize => "finalize",
+            AuditAction::Unlock => "unlock",
+            AuditAction::Reveal => "reveal",
+            AuditAction::BreakGlass => "break-glass",
+        })
+    }
+}
+
+/// One entry in the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 timestamp
+    pub timestamp: String,
+    /// Who triggered it: the CLI user, or the organizer signed in to the web UI
+    pub actor: String,
+    pub action: AuditAction,
+    pub pool: ExchangePool,
+    pub year: i32,
+    /// Seed the pairings were drawn from, sealed under the admin key (a plain
+    /// number in entries from before seeds were sealed)
+    #[serde(default, deserialize_with = "seed_from_number_or_text")]
+    pub seed: Option<String>,
+    /// `hash_pairings` of the resulting pairings, sealed the same way
+    pub result_hash: Option<String>,
+    /// The draw's commitment, for draws and regenerations
+    #[serde(default)]
+    pub commitment: Option<String>,
+    /// Free-form detail such as an unlock reason or what was revealed
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    /// An entry timestamped now, with no seed, hash, commitment or detail
+    pub fn new(actor: &str, action: AuditAction, pool: ExchangePool, year: i32) -> Self {
+        AuditEntry {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            actor: actor.to_string(),
+            action,
+            pool,
+            year,
+            seed: None,
+            result_hash: None,
+            commitment: None,
+            detail: None,
+        }
+    }
+
+    /// Records the seed a committed draw was made from and `hash_pairings` of
+    /// its result, sealed under `key` against the entry's commitment
+    ///
+    /// With the roster known, either one would give the draw away to anyone
+    /// who can read the log.
+    pub fn seal_result(&mut self, key: &VaultKey, seed: u64, exchange: &[(String, String)]) -> Result<()> {
+        let commitment = self.commitment.clone().context("Only a committed draw's result can be sealed")?;
+        self.seed = Some(key.encrypt(&seed.to_string(), &format!("audit-seed/{commitment}"))?);
+        self.result_hash = Some(key.encrypt(&hash_pairings(exchange), &format!("audit-result/{commitment}"))?);
+        Ok(())
+    }
+
+    /// The seed and result hash, decrypted with `key` if they were sealed
+    pub fn unsealed_result(&self, key: &VaultKey) -> Result<(Option<u64>, Option<String>)> {
+        let commitment = self.commitment.as_deref().unwrap_or_default();
+        let unseal = |value: &str, context: String| {
+            if vault::is_plaintext(value) {
+                Ok(value.to_string())
+            } else {
+                key.decrypt(value, &context)
+            }
+        };
+        let seed = self
+            .seed
+            .as_deref()
+            .map(|seed| unseal(seed, format!("audit-seed/{commitment}"))?.parse().context("Invalid seed in audit log"))
+            .transpose()?;
+        let result_hash = self
+            .result_hash
+            .as_deref()
+            .map(|hash| unseal(hash, format!("audit-result/{commitment}")))
+            .transpose()?;
+        Ok((seed, result_hash))
+    }
+}
+
+/// Entries written before seeds were sealed hold the seed as a JSON number
+fn seed_from_number_or_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seed {
+        Plain(u64),
+        Sealed(String),
+    }
+    Ok(Option::<Seed>::deserialize(deserializer)?.map(|seed| match seed {
+        Seed::Plain(seed) => seed.to_string(),
+        Seed::Sealed(sealed) => sealed,
+    }))
+}
+
+/// Hex SHA-256 of the pairings, independent of their order
+pub fn hash_pairings(exchange: &[(String, String)]) -> String {
+    let mut lines: Vec<String> = exchange
+        .iter()
+        .map(|(giver, receiver)| format!("{giver} -> {receiver}\n"))
+        .collect();
+    lines.sort();
+    to_hex(&Sha256::digest(lines.concat()))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Records that pairings for `pool` in `year` were shown to someone
+pub fn log_reveal(storage: &mut dyn Storage, actor: &str, pool: ExchangePool, year: i32, detail: &str) -> Result<()> {
+    let mut entry = AuditEntry::new(actor, AuditAction::Reveal, pool, year);
+    entry.detail = Some(detail.to_string());
+    storage.append_audit(&entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange() -> Vec<(String, String)> {
+        [("Alec", "Will"), ("Will", "Kaylee"), ("Kaylee", "Alec")]
+            .map(|(giver, receiver)| (giver.to_string(), receiver.to_string()))
+            .to_vec()
+    }
+
+    fn draw() -> AuditEntry {
+        AuditEntry {
+            commitment: Some("c0ffee".to_string()),
+            ..AuditEntry::new("alec", AuditAction::Draw, ExchangePool::IslandLife, 2024)
+        }
+    }
+
+    #[test]
+    fn sealed_result_unseals_with_the_admin_key() {
+        let key = VaultKey::generate().unwrap();
+        let mut entry = draw();
+        // Long enough that the sealed hex won't contain it by chance
+        let seed = 8_675_309_123_456;
+        entry.seal_result(&key, seed, &exchange()).unwrap();
+        assert!(!entry.seed.as_deref().unwrap().contains(&seed.to_string()));
+        assert!(!entry.result_hash.as_deref().unwrap().contains(&hash_pairings(&exchange())));
+        assert_eq!(entry.unsealed_result(&key).unwrap(), (Some(seed), Some(hash_pairings(&exchange()))));
+
+        assert!(entry.unsealed_result(&VaultKey::generate().unwrap()).is_err());
+        // Moving the sealed values to another draw's entry doesn't decrypt
+        entry.commitment = Some("decaf".to_string());
+        assert!(entry.unsealed_result(&key).is_err());
+    }
+
+    #[test]
+    fn uncommitted_entries_cant_be_sealed() {
+        let mut entry = AuditEntry::new("alec", AuditAction::Draw, ExchangePool::IslandLife, 2024);
+        assert!(entry.seal_result(&VaultKey::generate().unwrap(), 42, &exchange()).is_err());
+    }
+
+    #[test]
+    fn plain_seeds_from_older_entries_still_load() {
+        let json = r#"{"timestamp":"2023-12-01T10:00:00+00:00","actor":"alec","action":"draw","pool":"island",
+            "year":2023,"seed":42,"result_hash":"abc123","detail":null}"#;
+        let entry: AuditEntry = serde_json::from_str(json).unwrap();
+        assert_eq!(entry.seed.as_deref(), Some("42"));
+        let key = VaultKey::generate().unwrap();
+        assert_eq!(entry.unsealed_result(&key).unwrap(), (Some(42), Some("abc123".to_string())));
+    }
+
+    #[test]
+    fn pairing_hash_ignores_order() {
+        let mut reversed = exchange();
+        reversed.reverse();
+        assert_eq!(hash_pairings(&exchange()), hash_pairings(&reversed));
+        assert_ne!(hash_pairings(&exchange()), hash_pairings(&exchange()[..2]));
+    }
+}
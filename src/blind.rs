@@ -0,0 +1,125 @@
+use anyhow::Result;
+
+use crate::{
+    audit::{AuditAction, AuditEntry},
+    giftexchange::ExchangePool,
+    history::{self, HistoricalPairing},
+    season,
+    storage::Storage,
+    vault::{self, VaultKey},
+};
+
+/// Environment variable that turns on blind organizer mode when set to `1` or `true`
+pub const BLIND_ENV: &str = "CHRISTMAS_BLIND";
+
+/// Whether blind organizer mode is on
+///
+/// In blind mode the organizer can run draws without seeing them: this
+/// season's pairings are only shown through each giver's own reveal link, or
+/// through an audited break-glass reveal.
+pub fn is_blind() -> bool {
+    std::env::var(BLIND_ENV).is_ok_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+/// Whether pairings for `year` are hidden: in blind mode, those for the season
+/// today falls in and any later one
+///
+/// The season comes from today's date rather than `--season`, so naming an
+/// earlier season doesn't uncover the current draw.
+pub fn is_sealed(year: i32) -> Result<bool> {
+    Ok(is_blind() && year >= season::current_season()?)
+}
+
+/// Fails if the pairings for `pool` in `year` are hidden by blind mode
+pub fn ensure_unsealed(pool: ExchangePool, year: i32) -> Result<()> {
+    if is_sealed(year)? {
+        anyhow::bail!(
+            "Blind organizer mode is on, so the {pool} pairings for {year} are only shown through each \
+             giver's reveal link. Use `cargo run -- break-glass {} --reason <reason>` if they must be seen.",
+            pool.slug()
+        );
+    }
+    Ok(())
+}
+
+/// `history` without the pairings hidden by blind mode
+pub fn without_sealed(history: Vec<HistoricalPairing>) -> Result<Vec<HistoricalPairing>> {
+    let sealed_from = if is_blind() { Some(season::current_season()?) } else { None };
+    Ok(unsealed(history, sealed_from))
+}
+
+/// `history` without the pairings for `sealed_from` and later seasons, if it's set
+fn unsealed(history: Vec<HistoricalPairing>, sealed_from: Option<i32>) -> Vec<HistoricalPairing> {
+    match sealed_from {
+        Some(season) => history.into_iter().filter(|pairing| pairing.year < season).collect(),
+        None => history,
+    }
+}
+
+/// Returns every pairing for `pool` in `year`, hidden or not, decrypted with
+/// `key` and logging a break-glass entry with `actor` and `reason`
+pub fn break_glass(
+    storage: &mut dyn Storage,
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+    actor: &str,
+    reason: &str,
+) -> Result<Vec<(String, String)>> {
+    if reason.trim().is_empty() {
+        anyhow::bail!("A break-glass reveal needs a reason.");
+    }
+    let pairings = history::pool_pairings(&vault::decrypt_pairings(key, &storage.load_pairings()?)?, pool, year);
+    if pairings.is_empty() {
+        anyhow::bail!("There is no saved {pool} draw for {year}.");
+    }
+
+    let mut entry = AuditEntry::new(actor, AuditAction::BreakGlass, pool, year);
+    entry.detail = Some(reason.trim().to_string());
+    storage.append_audit(&entry)?;
+    Ok(pairings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempPath;
+
+    fn pairing(year: i32, giver: &str, receiver: &str) -> HistoricalPairing {
+        HistoricalPairing {
+            year,
+            pool: ExchangePool::IslandLife,
+            giver: giver.to_string(),
+            receiver: receiver.to_string(),
+        }
+    }
+
+    #[test]
+    fn sealing_hides_the_current_season_and_later() {
+        let history = vec![pairing(2024, "Alec", "Will"), pairing(2025, "Alec", "Grace"), pairing(2026, "Alec", "Noel")];
+        assert_eq!(unsealed(history.clone(), Some(2025)), vec![pairing(2024, "Alec", "Will")]);
+        assert_eq!(unsealed(history.clone(), None), history);
+    }
+
+    #[test]
+    fn break_glass_is_logged() {
+        let path = TempPath::new("blind-break-glass");
+        let mut storage = path.storage();
+        let key = VaultKey::generate().unwrap();
+        let exchange = [("Alec".to_string(), "Will".to_string()), ("Will".to_string(), "Alec".to_string())];
+        let pairings = vault::encrypt_exchange(&key, ExchangePool::IslandLife, 2025, &exchange).unwrap();
+        storage.save_draw(ExchangePool::IslandLife, 2025, &[], &pairings).unwrap();
+
+        // A reveal without a reason is refused before anything is logged
+        assert!(break_glass(&mut storage, &key, ExchangePool::IslandLife, 2025, "Kaylee", " ").is_err());
+        assert_eq!(storage.load_audit().unwrap(), vec![]);
+
+        let revealed = break_glass(&mut storage, &key, ExchangePool::IslandLife, 2025, "Kaylee", " lost slip ").unwrap();
+        assert_eq!(revealed, exchange);
+        let audit = storage.load_audit().unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].action, AuditAction::BreakGlass);
+        assert_eq!((audit[0].actor.as_str(), audit[0].year), ("Kaylee", 2025));
+        assert_eq!(audit[0].detail.as_deref(), Some("lost slip"));
+    }
+}
@@ -0,0 +1,967 @@
+use anyhow::{Context, Result};
+
+use crate::{
+    audit, blind,
+    commitment::DrawOpening,
+    data, draw, email,
+    exchange::{ExclusionReason, Participant},
+    history::{self, HistoricalPairing},
+    lifecycle,
+    messages::{self, Message, MessageBoard, MessageSender},
+    notify::{self, Event},
+    organizer,
+    randomness::{self, ContributionStatus},
+    reminders,
+    reveal, season, slips,
+    stats::PairStats,
+    storage, utils,
+    vault::{self, VaultKey},
+    wishlist::{self, NewWish, Priority, WishlistBoard, WishlistItem},
+};
+
+const USAGE: &str = "Usage:
+  cargo run <island|graber|pets> [--relax <reasons>] [--show]
+                                                 Draw an exchange, ignoring exclusions for the
+                                                 comma-separated reasons. Pairings are only
+                                                 printed with --show
+  cargo run links <pool> [--break-glass <reason>]
+                                                 Issue each giver a private reveal link. In blind
+                                                 organizer mode, only with a logged reason
+  cargo run reveal <token>                       Show who a reveal link's giver gives to
+  cargo run email <pool> [--dry-run <dir>]       Email each giver their assignment, or write the
+                                                 emails to <dir> instead of sending them
+  cargo run slips <pool> [--out <file>]          Printable slips, one per giver, folded to hide the name.
+                                                 Written to slips-<pool>-<year>.html by default
+  cargo run notify <pool> <deadline|exchange|completed>
+                                                 Tell the webhook the buy-by date or exchange day is
+                                                 near, or the exchange is done. Buy-by dates are off
+                                                 until set in data::deadline_for_pool
+  cargo run reminders [--dry-run]                Send the reminders due today, as the server does hourly
+  cargo run messages <token>                     A reveal link's threads with its recipient and its Santa
+  cargo run message <recipient|santa> <token> <text>
+                                                 Message who the link's giver gives to, or their Santa
+  cargo run wishlist <token> [--letter]          A reveal link's recipient's wishlist and its own; --letter
+                                                 keeps only ideas starting with this year's letter
+  cargo run wish add <token> <idea> [--link <url>] [--price <price>] [--priority <high|medium|low>]
+                                                 Add an idea to the link's giver's wishlist
+  cargo run wish remove <token> <number>         Remove an idea from the link's giver's wishlist
+  cargo run history person <name>                Who <name> gave to and received from, by year
+  cargo run history pool <pool> <year>           Every pairing in <pool> for <year>
+  cargo run history since <giver> <receiver>     Years since <giver> last drew <receiver>
+  cargo run stats [pool]                         How often each giver has drawn each receiver
+  cargo run status <pool>                        Whether the season's draw is finalized, and its unlocks
+  cargo run finalize <pool>                      Lock the season's draw so it can't be regenerated
+  cargo run unlock <pool> --reason <reason>      Unlock a finalized draw (admin only)
+  cargo run break-glass <pool> --reason <reason> Show every pairing, even in blind organizer mode
+  cargo run log [pool]                           Every draw, regeneration, finalize, unlock and reveal
+  cargo run exclusions [pool] [--reason <reason>]
+                                                 Exclusions in force for the season, by reason
+  cargo run commitment <pool>                    Commitment to the season's draw, to publish now
+  cargo run opening <pool>                       The draw's seed, roster and pairings, to publish later
+  cargo run verify <opening.json> <commitment>   Check a published opening against its commitment
+  cargo run seed open <pool> <reveal-by>         Open the season's seed round, taking reveals until <reveal-by>
+                                                 (YYYY-MM-DD); the draw waits until that day has passed
+  cargo run seed value <pool> <name>             A random value for <name> to keep, and its commitment
+  cargo run seed commit <pool> <name> <commitment>
+                                                 Record <name>'s commitment to the season's seed
+  cargo run seed reveal <pool> <name> <value>    Record <name>'s value once commitments are in, by the deadline
+  cargo run seed status <pool>                   Who has committed, revealed or forfeited
+  cargo run keygen                               Create an admin key for CHRISTMAS_ADMIN_KEY
+  cargo run organizer-key <name>                 Create a web UI sign-in key for an organizer
+  cargo run encrypt-history                      Encrypt pairings saved before encryption was added
+
+Options:
+  --season <year>    Season to act on. Defaults to the season today falls in, which
+                     starts on the CHRISTMAS_SEASON_CUTOVER date (MM-DD, default 02-01)
+
+Pairings are stored encrypted. Drawing and reading them needs the admin key in
+CHRISTMAS_ADMIN_KEY; opening a reveal link only needs the link.
+
+Set CHRISTMAS_BLIND=1 for blind organizer mode: this season's pairings are then only
+shown through reveal links or break-glass, which is logged with its reason.
+
+Exclusion reasons: spouse, sibling, household, last-year, request. When a pool can't
+be drawn, exclusions are relaxed in that order from last to first.";
+
+/// Runs the command line interface with the arguments after the program name
+pub fn run(args: &[String]) -> Result<()> {
+    let mut args = args.to_vec();
+    let season = match take_option(&mut args, "--season")? {
+        Some(value) => season::parse_season(&value)?,
+        None => season::current_season()?,
+    };
+
+    match args.first().map(String::as_str) {
+        Some("history") => show_history(&args[1..], season),
+        Some("stats") => show_stats(&args[1..]),
+        Some("status") => show_status(&args[1..], season),
+        Some("finalize") => finalize(&args[1..], season),
+        Some("unlock") => unlock(&args[1..], season),
+        Some("log") => show_log(&args[1..]),
+        Some("exclusions") => show_exclusions(&args[1..], season),
+        Some("links") => issue_links(&args[1..], season),
+        Some("reveal") => show_reveal(&args[1..]),
+        Some("email") => email_assignments(&args[1..], season),
+        Some("slips") => print_slips(&args[1..], season),
+        Some("notify") => send_notification(&args[1..], season),
+        Some("reminders") => send_reminders(&args[1..]),
+        Some("messages") => show_messages(&args[1..]),
+        Some("message") => send_message(&args[1..]),
+        Some("wishlist") => show_wishlists(&args[1..]),
+        Some("wish") => edit_wishlist(&args[1..]),
+        Some("break-glass") => break_glass(&args[1..], season),
+        Some("commitment") => show_commitment(&args[1..], season),
+        Some("opening") => show_opening(&args[1..], season),
+        Some("verify") => verify(&args[1..]),
+        Some("seed") => seed(&args[1..], season),
+        Some("keygen") => keygen(&args[1..]),
+        Some("organizer-key") => organizer_key(&args[1..]),
+        Some("encrypt-history") => encrypt_history(&args[1..]),
+        Some(pool_arg) => build_exchange(pool_arg, &args[1..], season),
+        None => anyhow::bail!("No pool specified.\n{USAGE}"),
+    }
+}
+
+/// Removes `flag` from `args`, returning whether it was given
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let given = args.iter().any(|arg| arg == flag);
+    args.retain(|arg| arg != flag);
+    given
+}
+
+/// Removes `--name <value>` from `args`, returning the value if it was given
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>> {
+    let Some(idx) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if idx + 1 >= args.len() {
+        anyhow::bail!("{name} needs a value.\n{USAGE}");
+    }
+    let value = args.remove(idx + 1);
+    args.remove(idx);
+    Ok(Some(value))
+}
+
+fn build_exchange(pool_arg: &str, args: &[String], season: i32) -> Result<()> {
+    let pool = utils::parse_pool_arg(pool_arg).map_err(|e| anyhow::anyhow!("{e}\n{USAGE}"))?;
+    let mut args = args.to_vec();
+    let show = take_flag(&mut args, "--show");
+    let relax = match &args[..] {
+        [] => vec![],
+        [flag, reasons] if flag == "--relax" => reasons
+            .split(',')
+            .map(|reason| ExclusionReason::from_slug(reason.trim()))
+            .collect::<Result<_>>()?,
+        _ => anyhow::bail!("Unexpected arguments: {}\n{USAGE}", args.join(" ")),
+    };
+    if show {
+        blind::ensure_unsealed(pool, season)?;
+    }
+
+    let mut storage = storage::open_default()?;
+    let exchange = draw::draw_exchange(storage.as_mut(), pool, season, &utils::current_user(), &relax)?;
+
+    // Print the exchange pairs only if asked, so the organizer isn't spoiled
+    println!("\nGift Exchange for {pool} {season}:");
+    println!("==========================");
+    if show {
+        audit::log_reveal(storage.as_mut(), &utils::current_user(), pool, season, "draw --show")?;
+        exchange.iter().for_each(|(sender, receiver)| {
+            println!("{sender} -> {receiver}");
+        });
+    } else {
+        println!(
+            "Drew {} pairings. Send each giver their link from `cargo run -- links {}`.",
+            exchange.len(),
+            pool.slug()
+        );
+    }
+
+    if let Some(commitment) = storage.load_commitment(pool, season)? {
+        println!("\nCommitment: {}", commitment.commitment);
+    }
+    let contributions = storage.load_seed_contributions(pool, season)?;
+    if !contributions.is_empty() {
+        let used = contributions.iter().filter(|c| c.status == ContributionStatus::Used).count();
+        println!("Seeded jointly from {used} contributions, the organizer's included.");
+        let forfeited: Vec<&str> = contributions
+            .iter()
+            .filter(|c| c.status == ContributionStatus::Forfeited)
+            .map(|c| c.participant.as_str())
+            .collect();
+        if !forfeited.is_empty() {
+            println!("Forfeited, not revealed in time: {}", forfeited.join(", "));
+        }
+    }
+
+    // Print the theme for this season
+    let theme = utils::theme_for_pool(storage.as_mut(), pool, season);
+    println!("\n{} for {pool} {season}: {theme}", theme.kind.label());
+
+    Ok(())
+}
+
+fn show_history(args: &[String], season: i32) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args[..] {
+        ["person", name] => {
+            let years = history::person_history(&load_history()?, name);
+            if years.is_empty() {
+                println!("No saved exchanges include {name}.");
+                return Ok(());
+            }
+            println!("\nExchange history for {name}:");
+            println!("==========================");
+            for year in years {
+                let gave_to = year.gave_to.as_deref().unwrap_or("-");
+                let received_from = year.received_from.as_deref().unwrap_or("-");
+                println!(
+                    "{} {}: gave to {gave_to}, received from {received_from}",
+                    year.year, year.pool
+                );
+            }
+        }
+        ["pool", pool_arg, year] => {
+            let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+            let year = season::parse_season(year)?;
+            blind::ensure_unsealed(pool, year)?;
+            let mut storage = storage::open_default()?;
+            let pairings = history::pool_pairings(&vault::load_pairings(storage.as_ref())?, pool, year);
+            if pairings.is_empty() {
+                println!("No saved pairings for {pool} in {year}.");
+                return Ok(());
+            }
+            audit::log_reveal(storage.as_mut(), &utils::current_user(), pool, year, "history pool")?;
+            println!("\nGift Exchange for {pool} {year}:");
+            println!("==========================");
+            for (giver, receiver) in pairings {
+                println!("{giver} -> {receiver}");
+            }
+        }
+        ["since", giver, receiver] => {
+            match history::years_since_drawn(&load_history()?, giver, receiver, season) {
+                Some(0) => println!("{giver} has {receiver} in the {season} season."),
+                Some(1) => println!("{giver} last drew {receiver} 1 year before {season} ({}).", season - 1),
+                Some(years) => println!(
+                    "{giver} last drew {receiver} {years} years before {season} ({}).",
+                    season - years
+                ),
+                None => println!("{giver} has never drawn {receiver}."),
+            }
+        }
+        _ => anyhow::bail!("Unknown history command.\n{USAGE}"),
+    }
+    Ok(())
+}
+
+fn show_stats(args: &[String]) -> Result<()> {
+    let pool = match args {
+        [] => None,
+        [pool_arg] => Some(utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?),
+        _ => anyhow::bail!("Unknown stats command.\n{USAGE}"),
+    };
+    let stats = PairStats::from_history(&load_history()?, pool);
+    if stats.people.is_empty() {
+        println!("No saved pairings to summarise.");
+        return Ok(());
+    }
+
+    let title = pool.map_or("all pools".to_string(), |pool| pool.to_string());
+    println!("\nPair frequency for {title} ({} years, rows give to columns):", stats.years.len());
+    let width = stats.people.iter().map(|p| p.len()).max().unwrap_or(0).max(5);
+    print!("{:width$}", "");
+    for receiver in &stats.people {
+        print!(" {receiver:>width$}");
+    }
+    println!();
+    for giver in &stats.people {
+        print!("{giver:width$}");
+        for receiver in &stats.people {
+            if giver == receiver {
+                print!(" {:>width$}", "-");
+            } else {
+                print!(" {:>width$}", stats.count(giver, receiver));
+            }
+        }
+        println!();
+    }
+
+    println!("\nDraw concentration (1.00 = always the same person):");
+    for giver in stats.concentration() {
+        println!(
+            "{:width$} {:.2} ({} draws, {} different people)",
+            giver.giver, giver.concentration, giver.draws, giver.distinct_receivers
+        );
+    }
+
+    let never_drawn = stats.never_drawn();
+    println!("\nNever drawn ({} pairs):", never_drawn.len());
+    for (giver, receiver) in never_drawn {
+        println!("{giver} -> {receiver}");
+    }
+    Ok(())
+}
+
+fn show_status(args: &[String], season: i32) -> Result<()> {
+    let [pool_arg] = args else {
+        anyhow::bail!("Unknown status command.\n{USAGE}");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    let storage = storage::open_default()?;
+
+    println!("{pool} {season}: {}", storage.exchange_status(pool, season)?);
+    for unlock in storage.load_unlocks(pool, season)? {
+        println!(
+            "  unlocked by {} at {}: {}",
+            unlock.unlocked_by, unlock.unlocked_at, unlock.reason
+        );
+    }
+    Ok(())
+}
+
+fn finalize(args: &[String], season: i32) -> Result<()> {
+    let [pool_arg] = args else {
+        anyhow::bail!("Unknown finalize command.\n{USAGE}");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    let event = lifecycle::finalize(storage::open_default()?.as_mut(), pool, season, &utils::current_user())?;
+    println!("Finalized the {pool} draw for {season}.");
+    notify::send_or_warn(&event);
+    Ok(())
+}
+
+fn unlock(args: &[String], season: i32) -> Result<()> {
+    let [pool_arg, flag, reason] = args else {
+        anyhow::bail!("Unknown unlock command.\n{USAGE}");
+    };
+    if flag != "--reason" {
+        anyhow::bail!("Unlocking a finalized draw needs a --reason.\n{USAGE}");
+    }
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    lifecycle::unlock(storage::open_default()?.as_mut(), pool, season, &utils::current_user(), reason)?;
+    println!("Unlocked the {pool} draw for {season}. It can be regenerated until it is finalized again.");
+    Ok(())
+}
+
+fn show_log(args: &[String]) -> Result<()> {
+    let pool = match args {
+        [] => None,
+        [pool_arg] => Some(utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?),
+        _ => anyhow::bail!("Unknown log command.\n{USAGE}"),
+    };
+    let entries: Vec<_> = storage::open_default()?
+        .load_audit()?
+        .into_iter()
+        .filter(|entry| pool.is_none_or(|pool| entry.pool == pool))
+        .collect();
+    if entries.is_empty() {
+        println!("The audit log is empty.");
+        return Ok(());
+    }
+
+    for entry in entries {
+        let mut line = format!(
+            "{} {:<11} {} {} by {}",
+            entry.timestamp, entry.action, entry.pool, entry.year, entry.actor
+        );
+        // Sealed seeds and results are only read back by `opening`, which checks them
+        let shown = |value: &String| {
+            if vault::is_plaintext(value) {
+                value.clone()
+            } else {
+                "(sealed)".to_string()
+            }
+        };
+        if let Some(seed) = &entry.seed {
+            line.push_str(&format!(" seed={}", shown(seed)));
+        }
+        if let Some(hash) = &entry.result_hash {
+            line.push_str(&format!(" result={}", shown(hash)));
+        }
+        if let Some(commitment) = &entry.commitment {
+            line.push_str(&format!(" commitment={commitment}"));
+        }
+        if let Some(detail) = &entry.detail {
+            line.push_str(&format!(" ({detail})"));
+        }
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn show_exclusions(args: &[String], season: i32) -> Result<()> {
+    let mut args = args.to_vec();
+    let reason = take_option(&mut args, "--reason")?
+        .map(|reason| ExclusionReason::from_slug(&reason))
+        .transpose()?;
+    let (participants, scope): (Vec<Participant>, String) = match &args[..] {
+        [] => (data::get_all_participants(), "all pools".to_string()),
+        [pool_arg] => {
+            let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+            (data::get_participants_by_pool(pool), pool.to_string())
+        }
+        _ => anyhow::bail!("Unknown exclusions command.\n{USAGE}"),
+    };
+
+    let exclusions: Vec<_> = participants
+        .iter()
+        .flat_map(|p| {
+            p.for_season(season)
+                .exclusions
+                .into_iter()
+                .map(|e| (p.name.clone(), e))
+        })
+        .filter(|(_, e)| reason.is_none_or(|reason| e.reason == reason))
+        .collect();
+    if exclusions.is_empty() {
+        println!("No exclusions are in force for {scope} in {season}.");
+        return Ok(());
+    }
+
+    println!("\nExclusions for {scope} in {season}:");
+    println!("==========================");
+    let width = exclusions
+        .iter()
+        .map(|(giver, e)| giver.len() + e.name.len())
+        .max()
+        .unwrap_or(0)
+        + 4;
+    for (giver, exclusion) in &exclusions {
+        let pair = format!("{giver} -> {}", exclusion.name);
+        println!("{pair:<width$}  {}", exclusion.reason);
+    }
+
+    println!("\nBy reason:");
+    for reason in ExclusionReason::RELAX_ORDER.into_iter().rev() {
+        let count = exclusions.iter().filter(|(_, e)| e.reason == reason).count();
+        if count > 0 {
+            println!("  {reason:<16} {count}");
+        }
+    }
+    Ok(())
+}
+
+fn issue_links(args: &[String], season: i32) -> Result<()> {
+    let mut args = args.to_vec();
+    let break_glass = take_option(&mut args, "--break-glass")?;
+    let [pool_arg] = &args[..] else {
+        anyhow::bail!("Usage: cargo run links <pool> [--break-glass <reason>]");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+
+    let issued = reveal::hand_out_tokens(
+        storage::open_default()?.as_mut(),
+        pool,
+        season,
+        &utils::current_user(),
+        break_glass.as_deref(),
+    )?;
+    println!("\nReveal links for {pool} {season}:");
+    println!("==========================");
+    for (giver, token) in issued {
+        println!("{giver}: {}", reveal::reveal_url(&token));
+    }
+    println!("\nLinks issued earlier for this draw no longer work.");
+    Ok(())
+}
+
+fn show_reveal(args: &[String]) -> Result<()> {
+    let [token] = args else {
+        anyhow::bail!("Usage: cargo run reveal <token>");
+    };
+    // Accept a whole link as well as the token at the end of it
+    let token = token.rsplit('/').next().unwrap_or(token);
+
+    let mut storage = storage::open_default()?;
+    let revealed = reveal::reveal(storage.as_mut(), token, Some(&utils::current_user()))?;
+    let theme = utils::theme_for_pool(storage.as_mut(), revealed.pool, revealed.year);
+    println!(
+        "{}, for {} {} you're giving to {}.",
+        revealed.giver, revealed.pool, revealed.year, revealed.receiver
+    );
+    println!("{}: {theme}", theme.kind.label());
+    if let Some(budget) = data::budget_for_pool(revealed.pool) {
+        println!("Budget: {budget}");
+    }
+    Ok(())
+}
+
+fn email_assignments(args: &[String], season: i32) -> Result<()> {
+    let mut args = args.to_vec();
+    let dry_run = take_option(&mut args, "--dry-run")?;
+    let [pool_arg] = &args[..] else {
+        anyhow::bail!("Unknown email command.\n{USAGE}");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    if dry_run.is_some() {
+        // The files show every pairing to whoever runs this
+        blind::ensure_unsealed(pool, season)?;
+    }
+
+    let mut storage = storage::open_default()?;
+    if dry_run.is_some() && storage.load_reveal_tokens(pool, season)?.is_empty() {
+        anyhow::bail!(
+            "No {pool} reveal links have been issued for {season}, and a dry run doesn't issue them. \
+             Issue them with `cargo run -- links {}` first.",
+            pool.slug()
+        );
+    }
+    let template = email::load_template()?;
+    let addresses = email::load_addresses()?;
+    let (emails, missing) =
+        email::assignment_emails(storage.as_mut(), &VaultKey::admin()?, pool, season, &template, &addresses)?;
+    if emails.is_empty() {
+        anyhow::bail!(
+            "Nobody in the {pool} exchange has an email address. Add them to {} (or the file in {}) as \
+             `Name = address` lines.",
+            email::DEFAULT_ADDRESSES_PATH,
+            email::ADDRESSES_ENV
+        );
+    }
+    if !missing.is_empty() {
+        eprintln!(
+            "Warning: No email address for {}. Add one to {} as a `Name = address` line.",
+            missing.join(", "),
+            email::DEFAULT_ADDRESSES_PATH
+        );
+    }
+
+    match dry_run {
+        Some(dir) => {
+            let written = email::write_dry_run(std::path::Path::new(&dir), pool, season, &emails)?;
+            audit::log_reveal(storage.as_mut(), &utils::current_user(), pool, season, &format!("emails written to {dir}"))?;
+            println!("Wrote {} emails to {dir}.", written.len());
+        }
+        None => {
+            send_emails(&emails)?;
+            audit::log_reveal(
+                storage.as_mut(),
+                &utils::current_user(),
+                pool,
+                season,
+                &format!("assignments emailed to {} givers", emails.len()),
+            )?;
+            println!("Emailed {} givers their {pool} {season} assignments.", emails.len());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "email")]
+fn send_emails(emails: &[email::AssignmentEmail]) -> Result<()> {
+    email::send(&email::SmtpSettings::from_env()?, emails)
+}
+
+#[cfg(not(feature = "email"))]
+fn send_emails(_emails: &[email::AssignmentEmail]) -> Result<()> {
+    anyhow::bail!("Sending email needs the email feature: cargo run --features email -- email <pool>. Use --dry-run <dir> to write the emails to files instead.")
+}
+
+fn print_slips(args: &[String], season: i32) -> Result<()> {
+    let mut args = args.to_vec();
+    let out = take_option(&mut args, "--out")?;
+    let [pool_arg] = &args[..] else {
+        anyhow::bail!("Unknown slips command.\n{USAGE}");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    // The file shows every pairing to whoever prints it
+    blind::ensure_unsealed(pool, season)?;
+
+    let mut storage = storage::open_default()?;
+    let slips = slips::slips(storage.as_mut(), &VaultKey::admin()?, pool, season)?;
+    let out = out.unwrap_or_else(|| format!("slips-{}-{season}.html", pool.slug()));
+    std::fs::write(&out, slips::document(pool, season, &slips)).with_context(|| format!("Failed to write {out}"))?;
+    audit::log_reveal(storage.as_mut(), &utils::current_user(), pool, season, &format!("printable slips written to {out}"))?;
+    println!("Wrote {} slips to {out}. Open it in a browser to print it or save it as a PDF.", slips.len());
+    Ok(())
+}
+
+fn send_notification(args: &[String], season: i32) -> Result<()> {
+    let [pool_arg, event] = args else {
+        anyhow::bail!("Unknown notify command.\n{USAGE}");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    let event = match event.as_str() {
+        "deadline" => {
+            let deadline = data::deadline_for_pool(pool, season)
+                .with_context(|| {
+                    format!(
+                        "The {pool} exchange has no buy-by date, so buy-by reminders are off. \
+                         Set one in data::deadline_for_pool."
+                    )
+                })?;
+            let days_left = (deadline - chrono::Local::now().date_naive()).num_days();
+            if days_left < 0 {
+                anyhow::bail!("The {pool} buy-by date, {deadline}, has passed.");
+            }
+            Event::DeadlineApproaching {
+                pool,
+                year: season,
+                deadline,
+                days_left,
+            }
+        }
+        "exchange" => {
+            let date = data::exchange_date_for_pool(pool, season)
+                .with_context(|| format!("The {pool} exchange has no date. Set one in data::exchange_date_for_pool."))?;
+            let days_left = (date - chrono::Local::now().date_naive()).num_days();
+            if days_left < 0 {
+                anyhow::bail!("The {pool} exchange, on {date}, has passed.");
+            }
+            Event::ExchangeApproaching {
+                pool,
+                year: season,
+                date,
+                days_left,
+            }
+        }
+        "completed" => Event::ExchangeCompleted { pool, year: season },
+        _ => anyhow::bail!("Unknown event '{event}'. Valid options are: deadline, exchange, completed"),
+    };
+
+    match notify::send(&event)? {
+        0 => anyhow::bail!("No notifiers are configured. Set {} to post to a webhook.", notify::WEBHOOK_URL_ENV),
+        _ => println!("Sent: {}", event.message()),
+    }
+    Ok(())
+}
+
+fn send_reminders(args: &[String]) -> Result<()> {
+    let mut args = args.to_vec();
+    let dry_run = take_flag(&mut args, "--dry-run");
+    if !args.is_empty() {
+        anyhow::bail!("Unknown reminders command.\n{USAGE}");
+    }
+    let offsets = reminders::reminder_days()?;
+    if offsets.is_empty() {
+        anyhow::bail!("Reminders are turned off by {}.", reminders::REMINDER_DAYS_ENV);
+    }
+    // Reminders always go out for the season today is in, like the server's
+    let today = chrono::Local::now().date_naive();
+    let season = season::SeasonCutover::from_env()?.season_for(today);
+    if let Some(note) = reminders::missing_deadlines_note(season) {
+        eprintln!("Note: {note}");
+    }
+
+    let mut storage = storage::open_default()?;
+    let reminders = if dry_run {
+        reminders::due(storage.as_ref(), season, today, &offsets)?
+    } else {
+        let notifiers = notify::notifiers()?;
+        if notifiers.is_empty() {
+            anyhow::bail!("No notifiers are configured. Set {} to post to a webhook.", notify::WEBHOOK_URL_ENV);
+        }
+        reminders::send_due(storage.as_mut(), &notifiers, season, today, &offsets)?
+    };
+
+    let verb = if dry_run { "Due" } else { "Sent" };
+    if reminders.is_empty() {
+        println!("{verb}: none.");
+    }
+    for reminder in reminders {
+        println!("{verb} ({} days before): {}", reminder.days_before, reminder.event.message());
+    }
+    Ok(())
+}
+
+fn show_messages(args: &[String]) -> Result<()> {
+    let [token] = args else {
+        anyhow::bail!("Usage: cargo run messages <token>");
+    };
+    let token = token.rsplit('/').next().unwrap_or(token);
+    print_board(&messages::board(storage::open_default()?.as_ref(), token)?);
+    Ok(())
+}
+
+fn send_message(args: &[String]) -> Result<()> {
+    let [to, token, text] = args else {
+        anyhow::bail!("Usage: cargo run message <recipient|santa> <token> <text>");
+    };
+    let token = token.rsplit('/').next().unwrap_or(token);
+    let mut storage = storage::open_default()?;
+    let board = match to.as_str() {
+        "recipient" => messages::message_recipient(storage.as_mut(), token, text)?,
+        "santa" => messages::message_santa(storage.as_mut(), token, text)?,
+        _ => anyhow::bail!("Messages go to your 'recipient' or your 'santa', not '{to}'."),
+    };
+    print_board(&board);
+    Ok(())
+}
+
+fn print_board(board: &MessageBoard) {
+    println!("With {} (they see you as \"Your Santa\"):", board.recipient);
+    print_thread(&board.my_recipient, MessageSender::Santa, &board.recipient);
+    println!("\nWith your Santa:");
+    print_thread(&board.my_santa, MessageSender::Recipient, "Your Santa");
+}
+
+fn print_thread(thread: &[Message], mine: MessageSender, other: &str) {
+    if thread.is_empty() {
+        println!("  No messages yet.");
+    }
+    for message in thread {
+        let from = if message.sender == mine { "You" } else { other };
+        println!("  [{}] {from}: {}", message.sent_on, message.body);
+    }
+}
+
+fn show_wishlists(args: &[String]) -> Result<()> {
+    let mut args = args.to_vec();
+    let by_letter = take_flag(&mut args, "--letter");
+    let [token] = &args[..] else {
+        anyhow::bail!("Usage: cargo run wishlist <token> [--letter]");
+    };
+    let token = token.rsplit('/').next().unwrap_or(token);
+    let board = wishlist::board(storage::open_default()?.as_mut(), token)?;
+    if by_letter && board.letter().is_none() {
+        anyhow::bail!("This year's theme isn't a letter, so there's nothing to filter by.");
+    }
+    print_wishlists(&board, by_letter);
+    Ok(())
+}
+
+fn edit_wishlist(args: &[String]) -> Result<()> {
+    let mut args = args.to_vec();
+    let link = take_option(&mut args, "--link")?.unwrap_or_default();
+    let price = take_option(&mut args, "--price")?.unwrap_or_default();
+    let priority = match take_option(&mut args, "--priority")? {
+        Some(priority) => Priority::from_slug(&priority)
+            .with_context(|| format!("Unknown priority '{priority}'. Valid options are: high, medium, low"))?,
+        None => Priority::Medium,
+    };
+    let mut storage = storage::open_default()?;
+    let board = match &args[..] {
+        [action, token, idea] if action == "add" => {
+            let wish = NewWish {
+                idea: idea.clone(),
+                link,
+                price,
+                priority,
+            };
+            wishlist::add_wish(storage.as_mut(), token.rsplit('/').next().unwrap_or(token), &wish)?
+        }
+        [action, token, number] if action == "remove" => {
+            let number: usize = number
+                .parse()
+                .ok()
+                .filter(|number| *number > 0)
+                .with_context(|| format!("'{number}' isn't an idea number. `wishlist <token>` lists them."))?;
+            wishlist::remove_wish(storage.as_mut(), token.rsplit('/').next().unwrap_or(token), number - 1)?
+        }
+        _ => anyhow::bail!("Unknown wish command.\n{USAGE}"),
+    };
+    print_wishlists(&board, false);
+    Ok(())
+}
+
+fn print_wishlists(board: &WishlistBoard, by_letter: bool) {
+    match board.letter().filter(|_| by_letter) {
+        Some(letter) => println!("{}'s wishlist, ideas starting with {letter}:", board.recipient),
+        None => println!("{}'s wishlist:", board.recipient),
+    }
+    let ideas = board.recipient_ideas(by_letter);
+    if board.recipient_items.is_empty() {
+        println!("  No ideas yet.");
+    } else if ideas.is_empty() {
+        println!("  None of their ideas fit this year's letter.");
+    }
+    for item in ideas {
+        println!("  {}", describe_wish(item));
+    }
+
+    println!("\nYour wishlist (only your Santa sees it):");
+    if board.my_items.is_empty() {
+        println!("  No ideas yet.");
+    }
+    for (number, item) in board.my_items.iter().enumerate() {
+        println!("  {}. {}", number + 1, describe_wish(item));
+    }
+}
+
+fn describe_wish(item: &WishlistItem) -> String {
+    let mut line = format!("[{}] {}", item.priority.slug(), item.idea);
+    if let Some(price) = &item.price {
+        line.push_str(&format!(", about {price}"));
+    }
+    if let Some(link) = &item.link {
+        line.push_str(&format!(" <{link}>"));
+    }
+    line
+}
+
+fn break_glass(args: &[String], season: i32) -> Result<()> {
+    let [pool_arg, flag, reason] = args else {
+        anyhow::bail!("Unknown break-glass command.\n{USAGE}");
+    };
+    if flag != "--reason" {
+        anyhow::bail!("A break-glass reveal needs a --reason.\n{USAGE}");
+    }
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    let pairings = blind::break_glass(
+        storage::open_default()?.as_mut(),
+        &VaultKey::admin()?,
+        pool,
+        season,
+        &utils::current_user(),
+        reason,
+    )?;
+
+    println!("\nGift Exchange for {pool} {season} (break-glass, logged):");
+    println!("==========================");
+    for (giver, receiver) in pairings {
+        println!("{giver} -> {receiver}");
+    }
+    Ok(())
+}
+
+fn show_commitment(args: &[String], season: i32) -> Result<()> {
+    let [pool_arg] = args else {
+        anyhow::bail!("Unknown commitment command.\n{USAGE}");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    let commitment = storage::open_default()?
+        .load_commitment(pool, season)?
+        .with_context(|| format!("There is no committed {pool} draw for {season}."))?;
+    println!("{}", commitment.commitment);
+    Ok(())
+}
+
+fn show_opening(args: &[String], season: i32) -> Result<()> {
+    let [pool_arg] = args else {
+        anyhow::bail!("Unknown opening command.\n{USAGE}");
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    blind::ensure_unsealed(pool, season)?;
+    let mut storage = storage::open_default()?;
+    let key = VaultKey::admin()?;
+    let opening = storage
+        .load_commitment(pool, season)?
+        .with_context(|| format!("There is no committed {pool} draw for {season}."))?
+        .open(&key)?;
+
+    // The draw's audit entry sealed its seed and result, which should match the opening
+    let commitment = opening.commitment();
+    let audit = storage.load_audit()?;
+    let drawn = audit.iter().rev().find(|e| e.commitment.as_ref() == Some(&commitment) && e.seed.is_some());
+    if let Some(entry) = drawn {
+        let expected = (Some(opening.seed), Some(audit::hash_pairings(&opening.exchange())));
+        if entry.unsealed_result(&key)? != expected {
+            anyhow::bail!("The {pool} {season} opening doesn't match the seed and result sealed in the audit log.");
+        }
+    }
+
+    audit::log_reveal(storage.as_mut(), &utils::current_user(), pool, season, "opening")?;
+    println!("{}", serde_json::to_string_pretty(&opening)?);
+    Ok(())
+}
+
+fn verify(args: &[String]) -> Result<()> {
+    let [path, commitment] = args else {
+        anyhow::bail!("Unknown verify command.\n{USAGE}");
+    };
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+    let opening: DrawOpening = serde_json::from_str(&contents).with_context(|| format!("Failed to parse {path}"))?;
+
+    opening.verify(commitment)?;
+    println!(
+        "The {} {} draw matches the commitment, and redrawing it from seed {} gives the same {} pairings.",
+        opening.pool,
+        opening.year,
+        opening.seed,
+        opening.pairings.len()
+    );
+    Ok(())
+}
+
+fn seed(args: &[String], season: i32) -> Result<()> {
+    let (command, pool_arg, rest) = match args {
+        [command, pool_arg, rest @ ..] => (command.as_str(), pool_arg, rest),
+        _ => anyhow::bail!("Unknown seed command.\n{USAGE}"),
+    };
+    let pool = utils::parse_pool_arg(pool_arg).map_err(anyhow::Error::msg)?;
+    let today = chrono::Local::now().date_naive();
+
+    match (command, rest) {
+        ("open", [reveal_by]) => {
+            let reveal_by = chrono::NaiveDate::parse_from_str(reveal_by, "%Y-%m-%d")
+                .with_context(|| format!("Invalid reveal deadline '{reveal_by}', expected YYYY-MM-DD"))?;
+            let mut storage = storage::open_default()?;
+            randomness::open_round(storage.as_mut(), &VaultKey::admin()?, pool, season, reveal_by, today)?;
+            println!("Opened the {pool} {season} seed round. Reveals are accepted until the end of {reveal_by}.");
+        }
+        ("value", [name]) => {
+            let (value, commitment) = randomness::new_value(pool, season, name)?;
+            println!("Value:      {value}");
+            println!("Commitment: {commitment}");
+            println!("\nSend the organizer only the commitment. Keep the value secret until every");
+            println!("commitment is in, then reveal it; if it isn't revealed by the reveal deadline, it's forfeited.");
+        }
+        ("commit", [name, commitment]) => {
+            let mut storage = storage::open_default()?;
+            randomness::record_commitment(storage.as_mut(), pool, season, name, commitment, today)?;
+            println!("Recorded {name}'s commitment to the {pool} {season} seed.");
+        }
+        ("reveal", [name, value]) => {
+            randomness::record_reveal(storage::open_default()?.as_mut(), pool, season, name, value, today)?;
+            println!("Recorded {name}'s value for the {pool} {season} seed.");
+        }
+        ("status", []) => {
+            let contributions = storage::open_default()?.load_seed_contributions(pool, season)?;
+            match randomness::reveal_deadline(&contributions)? {
+                _ if contributions.is_empty() => println!("The {pool} {season} seed round isn't open."),
+                Some(deadline) => println!("Reveals are accepted until the end of {deadline}.\n"),
+                None => {}
+            }
+            for c in contributions {
+                let revealed = match (&c.value, c.participant == randomness::ORGANIZER) {
+                    (Some(_), true) => "sealed until the opening".to_string(),
+                    (Some(value), false) => value.clone(),
+                    (None, _) => "-".to_string(),
+                };
+                println!("{:<16} {:<10} {} {revealed}", c.participant, c.status, c.commitment);
+            }
+        }
+        _ => anyhow::bail!("Unknown seed command.\n{USAGE}"),
+    }
+    Ok(())
+}
+
+fn keygen(args: &[String]) -> Result<()> {
+    if !args.is_empty() {
+        anyhow::bail!("Unknown keygen command.\n{USAGE}");
+    }
+    println!("{}={}", vault::ADMIN_KEY_ENV, VaultKey::generate()?.to_hex());
+    println!("\nKeep this key safe: without it, saved pairings can't be read or redrawn.");
+    Ok(())
+}
+
+fn organizer_key(args: &[String]) -> Result<()> {
+    let [name] = args else {
+        anyhow::bail!("Unknown organizer-key command.\n{USAGE}");
+    };
+    let (key, entry) = organizer::new_key(name)?;
+    println!("Add this entry to {} on the server (comma-separated):", organizer::ORGANIZERS_ENV);
+    println!("  {entry}");
+    println!("\nGive {} this key to sign in to the web UI with. It isn't stored anywhere:", name.trim());
+    println!("  {key}");
+    Ok(())
+}
+
+fn encrypt_history(args: &[String]) -> Result<()> {
+    if !args.is_empty() {
+        anyhow::bail!("Unknown encrypt-history command.\n{USAGE}");
+    }
+    let key = VaultKey::admin()?;
+    let encrypted = vault::encrypt_plaintext_pairings(storage::open_default()?.as_mut(), &key)?;
+    if encrypted == 0 {
+        println!("Every saved pairing is already encrypted.");
+    } else {
+        println!("Encrypted {encrypted} pairings.");
+    }
+    Ok(())
+}
+
+/// Loads every saved pairing from storage, leaving out any hidden by blind organizer mode
+fn load_history() -> Result<Vec<HistoricalPairing>> {
+    blind::without_sealed(vault::load_pairings(storage::open_default()?.as_ref())?)
+}
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::{self, to_hex},
+    exchange::{ExclusionReason, Participant, ParticipantGraph},
+    giftexchange::ExchangePool,
+    randomness::{self, SeedContribution},
+    vault::VaultKey,
+};
+
+/// One person in a committed draw, with who they couldn't draw
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RosterEntry {
+    pub name: String,
+    pub excludes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenedPairing {
+    pub giver: String,
+    pub receiver: String,
+}
+
+/// Everything a draw's commitment covers
+///
+/// Kept secret until the gifts are opened, then published so anyone can
+/// check it against the commitment and redraw it from the seed. The random
+/// salt stops the commitment being matched by trying every possible draw.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawOpening {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub seed: u64,
+    pub salt: String,
+    /// The roster as it was drawn: exclusions in force for the season, less any relaxed
+    pub roster: Vec<RosterEntry>,
+    pub pairings: Vec<OpenedPairing>,
+    /// Participants' contributions, when the seed was drawn jointly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributions: Vec<SeedContribution>,
+}
+
+/// A draw's commitment, with its opening encrypted under the admin key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawCommitment {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub commitment: String,
+    pub encrypted_opening: String,
+}
+
+impl DrawOpening {
+    /// The opening for a draw of `roster` from `seed`, with a new random salt
+    ///
+    /// `contributions` are the joint seed contributions the seed came from, if any.
+    pub fn new(
+        pool: ExchangePool,
+        year: i32,
+        seed: u64,
+        roster: &[Participant],
+        exchange: &[(String, String)],
+        contributions: Vec<SeedContribution>,
+    ) -> Result<Self> {
+        let mut salt = [0u8; 32];
+        getrandom::getrandom(&mut salt).context("Failed to generate a commitment salt")?;
+
+        let mut roster: Vec<RosterEntry> = roster
+            .iter()
+            .map(|p| {
+                let mut excludes: Vec<String> = p.exclusions.iter().map(|e| e.name.clone()).collect();
+                excludes.sort();
+                excludes.dedup();
+                RosterEntry {
+                    name: p.name.clone(),
+                    excludes,
+                }
+            })
+            .collect();
+        roster.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut pairings: Vec<OpenedPairing> = exchange
+            .iter()
+            .map(|(giver, receiver)| OpenedPairing {
+                giver: giver.clone(),
+                receiver: receiver.clone(),
+            })
+            .collect();
+        pairings.sort_by(|a, b| a.giver.cmp(&b.giver));
+
+        Ok(DrawOpening {
+            pool,
+            year,
+            seed,
+            salt: to_hex(&salt),
+            roster,
+            pairings,
+            contributions,
+        })
+    }
+
+    /// Hex SHA-256 of a canonical rendering of everything in the opening
+    pub fn commitment(&self) -> String {
+        let mut text = format!(
+            "christmas draw commitment v1\npool {}\nyear {}\nseed {}\nsalt {}\n",
+            self.pool.slug(),
+            self.year,
+            self.seed,
+            self.salt
+        );
+        let mut roster = self.roster.clone();
+        roster.sort_by(|a, b| a.name.cmp(&b.name));
+        for entry in roster {
+            let mut excludes = entry.excludes;
+            excludes.sort();
+            text.push_str(&format!("participant {} excludes [{}]\n", entry.name, excludes.join(", ")));
+        }
+        let mut pairings: Vec<String> = self
+            .pairings
+            .iter()
+            .map(|p| format!("pairing {} -> {}\n", p.giver, p.receiver))
+            .collect();
+        pairings.sort();
+        text.push_str(&pairings.concat());
+        for c in &self.contributions {
+            text.push_str(&format!(
+                "contribution {} {} {} {}\n",
+                c.participant,
+                c.status,
+                c.commitment,
+                c.value.as_deref().unwrap_or("-")
+            ));
+        }
+        to_hex(&Sha256::digest(text))
+    }
+
+    /// The pairings as giver, receiver tuples
+    pub fn exchange(&self) -> Vec<(String, String)> {
+        self.pairings
+            .iter()
+            .map(|p| (p.giver.clone(), p.receiver.clone()))
+            .collect()
+    }
+
+    /// The commitment, with this opening encrypted under `key` so it can be published later
+    pub fn seal(&self, key: &VaultKey) -> Result<DrawCommitment> {
+        let commitment = self.commitment();
+        Ok(DrawCommitment {
+            pool: self.pool,
+            year: self.year,
+            encrypted_opening: key.encrypt(&serde_json::to_string(self)?, &commitment)?,
+            commitment,
+        })
+    }
+
+    /// Checks this opening against `commitment`, and that redrawing the roster
+    /// from the seed gives the same pairings, so the draw wasn't re-rolled or edited
+    ///
+    /// A jointly seeded draw also has its seed checked against the participants'
+    /// revealed values.
+    pub fn verify(&self, commitment: &str) -> Result<()> {
+        if self.commitment() != commitment.trim().to_lowercase() {
+            anyhow::bail!("The draw doesn't match the commitment: the seed, roster or pairings differ from what was committed.");
+        }
+        if !self.contributions.is_empty() {
+            randomness::verify(&self.contributions, self.seed)?;
+        }
+
+        let roster = self
+            .roster
+            .iter()
+            .map(|entry| {
+                let excludes = entry.excludes.iter().map(|name| (name.as_str(), ExclusionReason::Request)).collect();
+                Participant::new(entry.name.clone(), vec![self.pool], excludes)
+            })
+            .collect();
+        let redrawn = ParticipantGraph::from_participants_seeded(roster, self.seed).build_exchange();
+        if audit::hash_pairings(&redrawn) != audit::hash_pairings(&self.exchange()) {
+            anyhow::bail!("The commitment matches, but redrawing from its seed gives different pairings.");
+        }
+        Ok(())
+    }
+}
+
+impl DrawCommitment {
+    /// Decrypts the opening with the admin key
+    pub fn open(&self, key: &VaultKey) -> Result<DrawOpening> {
+        let json = key
+            .decrypt(&self.encrypted_opening, &self.commitment)
+            .with_context(|| format!("Failed to decrypt the {} {} draw's opening", self.pool, self.year))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::roster;
+
+    /// An opening for a real draw of `roster` from a fixed seed, and its commitment
+    fn opening() -> (DrawOpening, String) {
+        let exchange = ParticipantGraph::from_participants_seeded(roster(), 2024).build_exchange();
+        let opening = DrawOpening::new(ExchangePool::IslandLife, 2024, 2024, &roster(), &exchange, vec![]).unwrap();
+        let commitment = opening.commitment();
+        (opening, commitment)
+    }
+
+    #[test]
+    fn good_opening_verifies() {
+        let (opening, commitment) = opening();
+        opening.verify(&commitment).unwrap();
+        // Published commitments may be pasted in upper case or with whitespace around them
+        opening.verify(&format!(" {} ", commitment.to_uppercase())).unwrap();
+    }
+
+    #[test]
+    fn sealed_opening_round_trips() {
+        let (opening, commitment) = opening();
+        let key = VaultKey::generate().unwrap();
+        let sealed = opening.seal(&key).unwrap();
+        assert_eq!(sealed.commitment, commitment);
+        assert_eq!(sealed.open(&key).unwrap(), opening);
+        assert!(sealed.open(&VaultKey::generate().unwrap()).is_err());
+    }
+
+    #[test]
+    fn edited_seed_is_rejected() {
+        let (mut opening, commitment) = opening();
+        opening.seed += 1;
+        assert!(opening.verify(&commitment).is_err());
+    }
+
+    #[test]
+    fn edited_roster_is_rejected() {
+        let (opening, commitment) = opening();
+
+        let mut dropped_exclusion = opening.clone();
+        dropped_exclusion.roster[0].excludes.clear();
+        assert!(dropped_exclusion.verify(&commitment).is_err());
+
+        let mut renamed = opening;
+        renamed.roster[1].name = "Grant".to_string();
+        assert!(renamed.verify(&commitment).is_err());
+    }
+
+    #[test]
+    fn edited_pairing_is_rejected() {
+        let (mut opening, commitment) = opening();
+        let first = opening.pairings[0].receiver.clone();
+        opening.pairings[0].receiver = opening.pairings[1].receiver.clone();
+        opening.pairings[1].receiver = first;
+        assert!(opening.verify(&commitment).is_err());
+    }
+
+    #[test]
+    fn edited_salt_is_rejected() {
+        let (mut opening, commitment) = opening();
+        opening.salt = to_hex(&[0u8; 32]);
+        assert!(opening.verify(&commitment).is_err());
+    }
+
+    #[test]
+    fn pairings_not_drawn_from_the_seed_are_rejected() {
+        // A commitment made to pairings the seed doesn't produce, as a re-rolled draw would be
+        let (mut opening, _) = opening();
+        let first = opening.pairings[0].receiver.clone();
+        opening.pairings[0].receiver = opening.pairings[1].receiver.clone();
+        opening.pairings[1].receiver = first;
+        let commitment = opening.commitment();
+        let error = opening.verify(&commitment).unwrap_err();
+        assert!(error.to_string().contains("redrawing"));
+    }
+}
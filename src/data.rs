@@ -1,105 +1,149 @@
-use crate::exchange::Participant;
+use chrono::NaiveDate;
+
+use crate::exchange::{
+    ExclusionReason::{self, Request},
+    Participant,
+};
+use crate::family::{self, FamilyRule, Relation, Relationship};
 use crate::giftexchange::ExchangePool;
+use crate::themes::{ThemeConfig, ThemeKind, GRABER_LETTERS};
 
-/// Returns all participants for the gift exchange
+/// Returns all participants for the gift exchange, with the exclusions derived
+/// from `get_relationships` by `family_rules` added to their own
 pub fn get_all_participants() -> Vec<Participant> {
+    family::derive_exclusions(listed_participants(), &get_relationships(), &family_rules())
+}
+
+/// Returns the relationships between participants that exclusions are derived from
+pub fn get_relationships() -> Vec<Relationship> {
+    vec![
+        Relationship::new("Claire", Relation::Spouse, "Duncan"),
+        Relationship::new("Noel", Relation::Spouse, "K-Lee"),
+        Relationship::new("Steve", Relation::Spouse, "Linda"),
+        Relationship::new("Anne", Relation::Spouse, "Eric"),
+        Relationship::new("Jim", Relation::Spouse, "Kari"),
+    ]
+}
+
+/// Returns which relationships mean two people may not give to each other
+pub fn family_rules() -> Vec<FamilyRule> {
+    vec![
+        FamilyRule::new(Relation::Spouse, ExclusionReason::Spouse),
+        FamilyRule::new(Relation::Partner, ExclusionReason::Spouse),
+        FamilyRule::new(Relation::Parent, ExclusionReason::Household),
+        FamilyRule::new(Relation::Child, ExclusionReason::Household),
+    ]
+}
+
+/// Returns the participants with the exclusions that aren't derived from relationships
+///
+/// Exclusions listed in `Participant::new` apply every season, with the reason
+/// each one exists. Time-bounded ones can be added with `Participant::excluding`
+/// (compiled only for tests until one is listed here, as are `Exclusion::in_years` and `until`), e.g.
+/// `.excluding(Exclusion::in_years("Grant", Request, &[2025]))` or
+/// `.excluding(Exclusion::until("Grant", Household, NaiveDate::from_ymd_opt(2026, 1, 31).unwrap()))`.
+/// Avoiding a repeat of last year's pairing only applies for one season, e.g.
+/// `.excluding(Exclusion::in_years("Grant", LastYear, &[2026]))`.
+/// Assignment emails go to the addresses in `addresses.txt` (see `email::load_addresses`),
+/// or to ones added here with `.with_email("claire@example.com")`.
+fn listed_participants() -> Vec<Participant> {
     vec![
         Participant::new(
             "Claire".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Duncan", "Chris"],
+            vec![("Chris", Request)],
         ),
         Participant::new(
             "Grant".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Noel"],
+            vec![("Noel", Request)],
         ),
         Participant::new(
             "Anne".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Eric", "Kari"],
+            vec![("Kari", Request)],
         ),
         Participant::new(
             "Duncan".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Claire", "Chris"],
+            vec![("Chris", Request)],
         ),
         Participant::new(
             "Noel".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["K-Lee", "Claire"],
+            vec![("Claire", Request)],
         ),
         Participant::new(
             "K-Lee".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Noel", "Jim"],
+            vec![("Jim", Request)],
         ),
         Participant::new(
             "Steve".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Linda", "Duncan"],
+            vec![("Duncan", Request)],
         ),
         Participant::new(
             "Linda".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Steve", "Alec"],
+            vec![("Alec", Request)],
         ),
         Participant::new(
             "Chris".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Eric"],
+            vec![("Eric", Request)],
         ),
         Participant::new(
             "Jim".to_string(),
             vec![ExchangePool::Grabergishimazureson],
-            vec!["Kari", "Anne"],
+            vec![("Anne", Request)],
         ),
         Participant::new(
             "Kari".to_string(),
             vec![ExchangePool::Grabergishimazureson],
-            vec!["Jim", "Linda"],
+            vec![("Linda", Request)],
         ),
         Participant::new(
             "Meaghann".to_string(),
             vec![ExchangePool::Grabergishimazureson],
-            vec!["Steve"],
+            vec![("Steve", Request)],
         ),
         Participant::new(
             "Alec".to_string(),
             vec![ExchangePool::Grabergishimazureson],
-            vec!["Meaghann"],
+            vec![("Meaghann", Request)],
         ),
         Participant::new(
             "Eric".to_string(),
             vec![ExchangePool::IslandLife, ExchangePool::Grabergishimazureson],
-            vec!["Anne", "K-Lee"],
+            vec![("K-Lee", Request)],
         ),
-        Participant::new("Stella".to_string(), vec![ExchangePool::Pets], vec!["Daisy"]),
+        Participant::new("Stella".to_string(), vec![ExchangePool::Pets], vec![("Daisy", Request)]),
         Participant::new(
             "Bailey".to_string(),
             vec![ExchangePool::Pets],
-            vec!["Luca"],
+            vec![("Luca", Request)],
         ),
-        Participant::new("Kitty".to_string(), vec![ExchangePool::Pets], vec!["Bailey"]),
+        Participant::new("Kitty".to_string(), vec![ExchangePool::Pets], vec![("Bailey", Request)]),
         Participant::new(
             "Charlie".to_string(),
             vec![ExchangePool::Pets],
-            vec!["Kona"],
+            vec![("Kona", Request)],
         ),
         Participant::new(
             "Astra".to_string(),
             vec![ExchangePool::Pets],
-            vec!["Lily"],
+            vec![("Lily", Request)],
         ),
-        Participant::new("Freya".to_string(), vec![ExchangePool::Pets], vec!["Stella"]),
-        Participant::new("Lily".to_string(), vec![ExchangePool::Pets], vec!["Kitty"]),
+        Participant::new("Freya".to_string(), vec![ExchangePool::Pets], vec![("Stella", Request)]),
+        Participant::new("Lily".to_string(), vec![ExchangePool::Pets], vec![("Kitty", Request)]),
         Participant::new(
             "Daisy".to_string(),
             vec![ExchangePool::Pets],
-            vec!["Astra"],
+            vec![("Astra", Request)],
         ),
-        Participant::new("Luca".to_string(), vec![ExchangePool::Pets], vec!["Charlie"]),
-        Participant::new("Kona".to_string(), vec![ExchangePool::Pets], vec!["Freya"]),
+        Participant::new("Luca".to_string(), vec![ExchangePool::Pets], vec![("Charlie", Request)]),
+        Participant::new("Kona".to_string(), vec![ExchangePool::Pets], vec![("Freya", Request)]),
     ]
 }
 
@@ -109,4 +153,45 @@ pub fn get_participants_by_pool(pool: ExchangePool) -> Vec<Participant> {
         .into_iter()
         .filter(|p| p.exchange_pools.contains(&pool))
         .collect()
-}
\ No newline at end of file
+}
+
+/// Returns the spending limit shown on each pool's reveal links, e.g. `Some("$25")`
+pub fn budget_for_pool(pool: ExchangePool) -> Option<&'static str> {
+    match pool {
+        ExchangePool::IslandLife => None,
+        ExchangePool::Grabergishimazureson => None,
+        ExchangePool::Pets => None,
+    }
+}
+
+/// Returns the last day to buy gifts for each pool's exchange in `season`,
+/// e.g. `NaiveDate::from_ymd_opt(season, 12, 20)`
+///
+/// No pool has one yet, so buy-by reminders and `notify <pool> deadline` are
+/// off, and emails and slips leave the date out, until one is set here.
+pub fn deadline_for_pool(pool: ExchangePool, _season: i32) -> Option<NaiveDate> {
+    match pool {
+        ExchangePool::IslandLife => None,
+        ExchangePool::Grabergishimazureson => None,
+        ExchangePool::Pets => None,
+    }
+}
+
+/// Returns the day each pool exchanges gifts in `season`, shown on printed slips
+///
+/// Every pool exchanges on Christmas Day; match on `_pool` to give one its own date.
+pub fn exchange_date_for_pool(_pool: ExchangePool, season: i32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(season, 12, 25)
+}
+
+/// Returns the gift theme configuration for each exchange pool
+///
+/// Other kinds of theme can be configured with `ThemeConfig::weighted`, e.g.
+/// `ThemeConfig::weighted(ThemeKind::Idea, &[("Handmade only", 2), ("Something local", 1)])`.
+pub fn theme_config_for_pool(pool: ExchangePool) -> ThemeConfig {
+    match pool {
+        ExchangePool::IslandLife => ThemeConfig::fixed(ThemeKind::Letter, "I"),
+        ExchangePool::Grabergishimazureson => ThemeConfig::letters(GRABER_LETTERS),
+        ExchangePool::Pets => ThemeConfig::fixed(ThemeKind::Letter, "P"),
+    }
+}
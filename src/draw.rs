@@ -0,0 +1,212 @@
+use anyhow::Result;
+
+use crate::{
+    audit::{AuditAction, AuditEntry},
+    commitment::DrawOpening,
+    data,
+    exchange::{ExclusionReason, Participant, ParticipantGraph},
+    giftexchange::ExchangePool,
+    lifecycle,
+    randomness::{self, ContributionStatus, SeedContribution},
+    reveal,
+    storage::Storage,
+    vault::{self, VaultKey},
+};
+
+/// Draws the exchange for `pool` in `year`, saves it with a commitment to the
+/// draw and writes an audit entry, all in one transaction
+///
+/// Fails if this year's draw has been finalized, or without the admin key the
+/// pairings are encrypted under. `actor` is recorded in the
+/// audit log as whoever triggered the draw. Exclusions for the reasons in
+/// `relax` are ignored, and if the pool still can't be drawn further reasons
+/// are relaxed in `ExclusionReason::RELAX_ORDER` until it can. If participants
+/// have committed to a joint seed, the draw is seeded from their reveals.
+pub fn draw_exchange(
+    storage: &mut dyn Storage,
+    pool: ExchangePool,
+    year: i32,
+    actor: &str,
+    relax: &[ExclusionReason],
+) -> Result<Vec<(String, String)>> {
+    lifecycle::ensure_can_redraw(storage, pool, year)?;
+    let key = VaultKey::admin()?;
+    let previously_drawn = !vault::givers(&storage.load_pairings()?, pool, year).is_empty();
+
+    // Get participants for the specified pool
+    let participants = data::get_participants_by_pool(pool);
+
+    // Only the exclusions in force this season, less any that were relaxed
+    let roster = |relaxed: &[ExclusionReason]| -> Vec<Participant> {
+        participants
+            .iter()
+            .map(|p| p.for_season(year).relaxing(relaxed))
+            .collect()
+    };
+
+    let joint_seed = randomness::seed_for_draw(storage, &key, pool, year, chrono::Local::now().date_naive())?;
+    let (exchange, seed, relaxed) = draw_relaxing(pool, roster, relax, joint_seed.as_ref().map(|(seed, _)| *seed));
+    let contributions = joint_seed.map(|(_, contributions)| contributions).unwrap_or_default();
+    let opening = DrawOpening::new(pool, year, seed, &roster(&relaxed), &exchange, contributions.clone())?;
+
+    let action = if previously_drawn {
+        AuditAction::Regenerate
+    } else {
+        AuditAction::Draw
+    };
+    // The seed and a plain hash of the pairings would give the draw away, so
+    // they're sealed under the admin key and only the salted commitment is in the clear
+    let mut entry = AuditEntry::new(actor, action, pool, year);
+    entry.commitment = Some(opening.commitment());
+    entry.seal_result(&key, seed, &exchange)?;
+    let mut details = vec![];
+    if !relaxed.is_empty() {
+        details.push(relaxed_detail(&relaxed));
+    }
+    if !contributions.is_empty() {
+        details.push(joint_seed_detail(&contributions));
+    }
+    if !details.is_empty() {
+        entry.detail = Some(details.join("; "));
+    }
+
+    // Saved together, so a failure part way can't leave the new pairings with
+    // the old commitment or reveal links
+    let pairings = vault::encrypt_exchange(&key, pool, year, &exchange)?;
+    let commitment = opening.seal(&key)?;
+    storage.in_transaction(&mut |storage| {
+        storage.save_draw(pool, year, &participants, &pairings)?;
+        storage.save_commitment(&commitment)?;
+        reveal::reencrypt_tokens(storage, &key, pool, year, &exchange)?;
+        if previously_drawn {
+            // Each thread is with whoever gave to the recipient, who may have changed
+            storage.clear_messages(pool, year)?;
+        }
+        storage.append_audit(&entry)
+    })?;
+
+    Ok(exchange)
+}
+
+/// Draws the roster `roster` gives with the reasons in `relax` relaxed, relaxing
+/// further reasons in `ExclusionReason::RELAX_ORDER` until a draw is possible
+///
+/// Draws from `seed` if one is given. Returns the exchange, the seed it was
+/// drawn from and every reason that was relaxed.
+fn draw_relaxing(
+    pool: ExchangePool,
+    roster: impl Fn(&[ExclusionReason]) -> Vec<Participant>,
+    relax: &[ExclusionReason],
+    seed: Option<u64>,
+) -> (Vec<(String, String)>, u64, Vec<ExclusionReason>) {
+    let mut relaxed = relax.to_vec();
+    let mut graph = match seed {
+        Some(seed) => ParticipantGraph::from_participants_seeded(roster(&relaxed), seed),
+        None => ParticipantGraph::from_participants(roster(&relaxed)),
+    };
+    loop {
+        if let Some(exchange) = graph.find_exchange() {
+            return (exchange, graph.seed(), relaxed);
+        }
+        match ExclusionReason::RELAX_ORDER.into_iter().find(|r| !relaxed.contains(r)) {
+            Some(reason) => {
+                eprintln!("Warning: The {pool} draw isn't possible with every exclusion. Relaxing {reason} exclusions.");
+                relaxed.push(reason);
+                graph = ParticipantGraph::from_participants_seeded(roster(&relaxed), graph.seed());
+            }
+            None => return (graph.build_exchange(), graph.seed(), relaxed),
+        }
+    }
+}
+
+/// Audit detail naming the exclusion reasons a draw relaxed
+fn relaxed_detail(relaxed: &[ExclusionReason]) -> String {
+    let reasons: Vec<&str> = relaxed.iter().map(|r| r.slug()).collect();
+    format!("relaxed exclusions: {}", reasons.join(", "))
+}
+
+/// Audit detail naming whose reveals seeded a joint draw and who forfeited
+fn joint_seed_detail(contributions: &[SeedContribution]) -> String {
+    let names = |status| {
+        contributions
+            .iter()
+            .filter(|c| c.status == status)
+            .map(|c| c.participant.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut detail = format!("joint seed from {}", names(ContributionStatus::Used));
+    let forfeited = names(ContributionStatus::Forfeited);
+    if !forfeited.is_empty() {
+        detail.push_str(&format!(", forfeited by {forfeited}"));
+    }
+    detail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{exchange::ExclusionReason::*, testing::roster};
+
+    fn draw(roster: Vec<Participant>, relax: &[ExclusionReason]) -> (Vec<(String, String)>, Vec<ExclusionReason>) {
+        let (exchange, seed, relaxed) = draw_relaxing(
+            ExchangePool::IslandLife,
+            |relaxed| roster.iter().map(|p| p.relaxing(relaxed)).collect(),
+            relax,
+            Some(2024),
+        );
+        assert_eq!(seed, 2024);
+        (exchange, relaxed)
+    }
+
+    fn gives(exchange: &[(String, String)], giver: &str, receiver: &str) -> bool {
+        exchange.iter().any(|(g, r)| g == giver && r == receiver)
+    }
+
+    #[test]
+    fn possible_draws_relax_nothing() {
+        let (exchange, relaxed) = draw(roster(), &[]);
+        assert_eq!(exchange.len(), 5);
+        assert_eq!(relaxed, vec![]);
+        assert!(!gives(&exchange, "Alec", "Kaylee") && !gives(&exchange, "Kaylee", "Alec"));
+    }
+
+    #[test]
+    fn requests_are_relaxed_before_spouses() {
+        // Alec has asked not to give to anyone he isn't married to, so only relaxing requests makes it possible
+        let mut roster = roster();
+        roster[0] = Participant::new(
+            "Alec".to_string(),
+            vec![ExchangePool::IslandLife],
+            vec![("Kaylee", Spouse), ("Will", Request), ("Grace", Request), ("Noel", Request)],
+        );
+        let (exchange, relaxed) = draw(roster, &[]);
+        assert_eq!(relaxed, vec![Request]);
+        assert!(!gives(&exchange, "Alec", "Kaylee") && !gives(&exchange, "Kaylee", "Alec"));
+        assert_eq!(relaxed_detail(&relaxed), "relaxed exclusions: request");
+    }
+
+    #[test]
+    fn spouses_are_relaxed_only_when_nothing_else_works() {
+        let couple = |name: &str, spouse: &str, household: &str| {
+            Participant::new(
+                name.to_string(),
+                vec![ExchangePool::IslandLife],
+                vec![(spouse, Spouse), (household, Household)],
+            )
+        };
+        // Three people who all live together, two of them married
+        let roster = vec![
+            couple("Alec", "Kaylee", "Will"),
+            couple("Kaylee", "Alec", "Will"),
+            Participant::new("Will".to_string(), vec![ExchangePool::IslandLife], vec![("Alec", Household)]),
+        ];
+        let (exchange, relaxed) = draw(roster, &[LastYear]);
+        assert_eq!(exchange.len(), 3);
+        assert_eq!(relaxed, vec![LastYear, Request, Household, Sibling, Spouse]);
+        assert_eq!(
+            relaxed_detail(&relaxed),
+            "relaxed exclusions: last-year, request, household, sibling, spouse"
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    data, history,
+    giftexchange::ExchangePool,
+    qr, reveal,
+    storage::Storage,
+    utils,
+    vault::{self, VaultKey},
+};
+
+/// Environment variable with the path of the assignment email template
+pub const TEMPLATE_ENV: &str = "CHRISTMAS_EMAIL_TEMPLATE";
+
+/// Where the assignment email template is read from when `CHRISTMAS_EMAIL_TEMPLATE` isn't set
+pub const DEFAULT_TEMPLATE_PATH: &str = "templates/assignment.txt";
+
+/// Used when there's no template file, e.g. when running from another directory
+const BUILTIN_TEMPLATE: &str = include_str!("../templates/assignment.txt");
+
+/// Environment variable with the path of the file of participants' email addresses
+pub const ADDRESSES_ENV: &str = "CHRISTMAS_EMAIL_ADDRESSES";
+
+/// Where addresses are read from when `CHRISTMAS_EMAIL_ADDRESSES` isn't set, if it exists
+pub const DEFAULT_ADDRESSES_PATH: &str = "addresses.txt";
+
+/// Environment variable with the address emails are sent from
+pub const FROM_ENV: &str = "CHRISTMAS_EMAIL_FROM";
+
+const DEFAULT_FROM: &str = "Christmas Gift Exchange <christmas@localhost>";
+
+/// An assignment email, ready to send
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssignmentEmail {
+    pub giver: String,
+    pub address: String,
+    pub subject: String,
+    pub body: String,
+    /// A PNG QR code of the giver's reveal link, attached to the email
+    pub qr_png: Vec<u8>,
+}
+
+/// The address emails are sent from, from `CHRISTMAS_EMAIL_FROM`
+pub fn from_address() -> String {
+    std::env::var(FROM_ENV).unwrap_or_else(|_| DEFAULT_FROM.to_string())
+}
+
+/// The template from `CHRISTMAS_EMAIL_TEMPLATE` or `templates/assignment.txt`,
+/// falling back to the built-in copy if the default file isn't there
+pub fn load_template() -> Result<String> {
+    match std::env::var(TEMPLATE_ENV) {
+        Ok(path) => std::fs::read_to_string(&path).with_context(|| format!("Failed to read the email template {path}")),
+        Err(_) => Ok(std::fs::read_to_string(DEFAULT_TEMPLATE_PATH).unwrap_or_else(|_| BUILTIN_TEMPLATE.to_string())),
+    }
+}
+
+/// Participants' addresses from the file in `CHRISTMAS_EMAIL_ADDRESSES`, or
+/// `addresses.txt` if it exists, as `Name = address` lines
+///
+/// These are kept out of `data.rs` so addresses aren't committed with the code.
+pub fn load_addresses() -> Result<Vec<(String, String)>> {
+    let (path, contents) = match std::env::var(ADDRESSES_ENV) {
+        Ok(path) => {
+            let contents =
+                std::fs::read_to_string(&path).with_context(|| format!("Failed to read the addresses file {path}"))?;
+            (path, contents)
+        }
+        Err(_) => match std::fs::read_to_string(DEFAULT_ADDRESSES_PATH) {
+            Ok(contents) => (DEFAULT_ADDRESSES_PATH.to_string(), contents),
+            Err(_) => return Ok(vec![]),
+        },
+    };
+    parse_addresses(&contents).with_context(|| format!("Invalid addresses file {path}"))
+}
+
+/// Parses `Name = address` lines, skipping blank lines and `#` comments
+fn parse_addresses(contents: &str) -> Result<Vec<(String, String)>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            let (name, address) = line
+                .split_once('=')
+                .filter(|(name, address)| !name.trim().is_empty() && address.contains('@'))
+                .with_context(|| format!("Line {} should look like `Claire = claire@example.com`", index + 1))?;
+            Ok((name.trim().to_string(), address.trim().to_string()))
+        })
+        .collect()
+}
+
+/// An email for every giver in the saved draw for `pool` in `year`, rendered from `template`
+///
+/// Givers' addresses come from `addresses`, or from `data.rs` for those not in
+/// it. Returns the emails and the givers who have no email address. Each email
+/// links to the giver's reveal page, issuing links if there are none yet.
+/// Template lines using `{budget}` or `{deadline}` are left out if the pool has none.
+pub fn assignment_emails(
+    storage: &mut dyn Storage,
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+    template: &str,
+    addresses: &[(String, String)],
+) -> Result<(Vec<AssignmentEmail>, Vec<String>)> {
+    let mut pairings = history::pool_pairings(&vault::decrypt_pairings(key, &storage.load_pairings()?)?, pool, year);
+    if pairings.is_empty() {
+        anyhow::bail!("There is no saved {pool} draw for {year} to email.");
+    }
+    pairings.sort();
+
+    let tokens = reveal::current_tokens(storage, key, pool, year)?;
+    let participants: Vec<_> = data::get_participants_by_pool(pool)
+        .into_iter()
+        .map(|participant| match addresses.iter().find(|(name, _)| *name == participant.name) {
+            Some((_, address)) => participant.with_email(address),
+            None => participant,
+        })
+        .collect();
+    let theme = utils::theme_for_pool(storage, pool, year);
+    let budget = data::budget_for_pool(pool).map(str::to_string);
+    let deadline = data::deadline_for_pool(pool, year).map(|date| date.format("%A, %B %-d").to_string());
+    let unset: Vec<&str> = [("budget", &budget), ("deadline", &deadline)]
+        .into_iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| name)
+        .collect();
+    let template = without_lines_using(template, &unset);
+
+    let mut emails = vec![];
+    let mut missing = vec![];
+    for (giver, receiver) in pairings {
+        let Some(address) = participants.iter().find(|p| p.name == giver).and_then(|p| p.email.clone()) else {
+            missing.push(giver);
+            continue;
+        };
+        let token = tokens
+            .iter()
+            .find(|(name, _)| *name == giver)
+            .map(|(_, token)| token)
+            .with_context(|| format!("{giver} has no reveal link"))?;
+        let reveal_url = reveal::reveal_url(token);
+        let values = [
+            ("giver", giver.clone()),
+            ("receiver", receiver),
+            ("pool", pool.to_string()),
+            ("year", year.to_string()),
+            ("theme_label", theme.kind.label().to_string()),
+            ("theme", theme.value.clone()),
+            ("budget", budget.clone().unwrap_or_default()),
+            ("deadline", deadline.clone().unwrap_or_default()),
+            ("reveal_url", reveal_url.clone()),
+        ];
+        let (subject, body) = render(&template, &values)?;
+        emails.push(AssignmentEmail {
+            giver,
+            address,
+            subject,
+            body,
+            qr_png: qr::png(&reveal_url)?,
+        });
+    }
+    Ok((emails, missing))
+}
+
+/// `template` without the lines that use any of the `unset` placeholders
+fn without_lines_using(template: &str, unset: &[&str]) -> String {
+    template
+        .split_inclusive('\n')
+        .filter(|line| !unset.iter().any(|name| line.contains(&format!("{{{name}}}"))))
+        .collect()
+}
+
+/// Fills in `template` and splits off its `Subject:` line
+fn render(template: &str, values: &[(&str, String)]) -> Result<(String, String)> {
+    let filled = utils::fill_template(template, "email template", values)?;
+    let (first, body) = filled.split_once('\n').unwrap_or((&filled, ""));
+    let subject = first
+        .strip_prefix("Subject:")
+        .context("The email template must start with a \"Subject:\" line")?;
+    Ok((subject.trim().to_string(), body.trim_start_matches(['\r', '\n']).to_string()))
+}
+
+/// Writes each email to `<dir>/<pool>-<year>-<giver>.eml` instead of sending
+/// it, with its QR code next to it in `<pool>-<year>-<giver>-qr.png`
+pub fn write_dry_run(dir: &Path, pool: ExchangePool, year: i32, emails: &[AssignmentEmail]) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let from = from_address();
+    emails
+        .iter()
+        .map(|email| {
+            let path = dir.join(format!("{}-{year}-{}.eml", pool.slug(), email.giver));
+            let qr_path = dir.join(format!("{}-{year}-{}-qr.png", pool.slug(), email.giver));
+            std::fs::write(&qr_path, &email.qr_png).with_context(|| format!("Failed to write {}", qr_path.display()))?;
+            let contents = format!(
+                "From: {from}\nTo: {} <{}>\nSubject: {}\n\n{}",
+                email.giver, email.address, email.subject, email.body
+            );
+            std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(feature = "email")]
+pub use smtp::{send, SmtpSettings};
+
+#[cfg(feature = "email")]
+mod smtp {
+    use anyhow::{Context, Result};
+    use lettre::{
+        message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart},
+        transport::smtp::authentication::Credentials,
+        Message, SmtpTransport, Transport,
+    };
+
+    use super::AssignmentEmail;
+
+    /// How to connect to the SMTP server, from `CHRISTMAS_SMTP_*`
+    ///
+    /// `CHRISTMAS_SMTP_SECURITY` is `starttls` (the default), `tls`, or `none`
+    /// for a local catcher such as MailHog on port 1025.
+    pub struct SmtpSettings {
+        host: String,
+        port: Option<u16>,
+        security: String,
+        credentials: Option<Credentials>,
+    }
+
+    impl SmtpSettings {
+        pub fn from_env() -> Result<Self> {
+            let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+            let port = var("CHRISTMAS_SMTP_PORT")
+                .map(|port| port.parse().context("CHRISTMAS_SMTP_PORT must be a port number"))
+                .transpose()?;
+            let credentials = match (var("CHRISTMAS_SMTP_USERNAME"), var("CHRISTMAS_SMTP_PASSWORD")) {
+                (Some(username), Some(password)) => Some(Credentials::new(username, password)),
+                (None, None) => None,
+                _ => anyhow::bail!("Set both CHRISTMAS_SMTP_USERNAME and CHRISTMAS_SMTP_PASSWORD, or neither."),
+            };
+            Ok(SmtpSettings {
+                host: var("CHRISTMAS_SMTP_HOST").context("Set CHRISTMAS_SMTP_HOST to the SMTP server to send through")?,
+                port,
+                security: var("CHRISTMAS_SMTP_SECURITY").unwrap_or_else(|| "starttls".to_string()),
+                credentials,
+            })
+        }
+
+        fn transport(&self) -> Result<SmtpTransport> {
+            let mut builder = match self.security.as_str() {
+                "starttls" => SmtpTransport::starttls_relay(&self.host)?,
+                "tls" => SmtpTransport::relay(&self.host)?,
+                "none" => SmtpTransport::builder_dangerous(&self.host),
+                other => anyhow::bail!("CHRISTMAS_SMTP_SECURITY must be starttls, tls or none, not '{other}'"),
+            };
+            if let Some(port) = self.port {
+                builder = builder.port(port);
+            }
+            if let Some(credentials) = &self.credentials {
+                builder = builder.credentials(credentials.clone());
+            }
+            Ok(builder.build())
+        }
+    }
+
+    /// Sends each email through the SMTP server in `settings`, with its QR code
+    /// attached, stopping at the first failure
+    pub fn send(settings: &SmtpSettings, emails: &[AssignmentEmail]) -> Result<()> {
+        let from: Mailbox = super::from_address()
+            .parse()
+            .with_context(|| format!("{} isn't a valid address", super::FROM_ENV))?;
+        let transport = settings.transport()?;
+        let png = ContentType::parse("image/png")?;
+        for email in emails {
+            let to = Mailbox::new(
+                Some(email.giver.clone()),
+                email
+                    .address
+                    .parse()
+                    .with_context(|| format!("{}'s email address {} isn't valid", email.giver, email.address))?,
+            );
+            let message = Message::builder()
+                .from(from.clone())
+                .to(to)
+                .subject(&email.subject)
+                .multipart(
+                    MultiPart::mixed()
+                        .singlepart(SinglePart::plain(email.body.clone()))
+                        .singlepart(Attachment::new("reveal-link.png".to_string()).body(email.qr_png.clone(), png.clone())),
+                )?;
+            transport
+                .send(&message)
+                .with_context(|| format!("Failed to email {}", email.giver))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Vec<(&'static str, String)> {
+        vec![("giver", "Claire".to_string()), ("receiver", "Duncan".to_string())]
+    }
+
+    #[test]
+    fn render_splits_off_the_subject() {
+        let (subject, body) = render("Subject:  Hi {giver}\r\n\r\nYou give to {receiver}.\n", &values()).unwrap();
+        assert_eq!(subject, "Hi Claire");
+        assert_eq!(body, "You give to Duncan.\n");
+        assert_eq!(render("Subject: Hi", &values()).unwrap(), ("Hi".to_string(), String::new()));
+    }
+
+    #[test]
+    fn render_needs_a_subject_and_known_placeholders() {
+        let error = render("Hi {giver}\nSubject: Hi", &values()).unwrap_err();
+        assert!(error.to_string().contains("Subject:"));
+        let error = render("Subject: Hi\n{reciever}", &values()).unwrap_err();
+        assert!(error.to_string().contains("{reciever}"));
+    }
+
+    #[test]
+    fn unset_lines_are_left_out() {
+        let template = "Subject: Hi\nBudget: {budget}\r\nBuy by: {deadline}\nTheme: {theme}\n";
+        assert_eq!(without_lines_using(template, &["budget"]), "Subject: Hi\nBuy by: {deadline}\nTheme: {theme}\n");
+        assert_eq!(without_lines_using(template, &[]), template);
+    }
+
+    #[test]
+    fn addresses_parse_name_and_address_lines() {
+        let contents = "# Island Life\nClaire = claire@example.com\n\n  K-Lee=klee@example.com  \n";
+        assert_eq!(
+            parse_addresses(contents).unwrap(),
+            [
+                ("Claire".to_string(), "claire@example.com".to_string()),
+                ("K-Lee".to_string(), "klee@example.com".to_string())
+            ]
+        );
+        for invalid in ["Claire claire@example.com", "= claire@example.com", "Claire = claire"] {
+            assert!(parse_addresses(invalid).is_err(), "{invalid} was accepted");
+        }
+    }
+}
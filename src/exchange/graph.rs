@@ -5,6 +5,7 @@ use super::participant::Participant;
 pub struct ParticipantGraph {
     edges: HashMap<String, Vec<String>>,
     participants: HashMap<String, Participant>,
+    seed: u64,
 }
 
 impl ParticipantGraph {
@@ -12,11 +13,18 @@ impl ParticipantGraph {
         Self {
             participants: HashMap::new(),
             edges: HashMap::new(),
+            seed: fastrand::u64(..),
         }
     }
 
     pub fn from_participants(participants: Vec<Participant>) -> Self {
-        let mut graph = Self::new();
+        Self::from_participants_seeded(participants, fastrand::u64(..))
+    }
+
+    /// Builds a graph whose draws are reproducible: the same participants and
+    /// seed always produce the same exchange.
+    pub fn from_participants_seeded(participants: Vec<Participant>, seed: u64) -> Self {
+        let mut graph = Self { seed, ..Self::new() };
         participants.iter().for_each(|p| {
             graph.add_participant(p.clone());
         });
@@ -29,14 +37,28 @@ impl ParticipantGraph {
             .insert(participant.name.clone(), participant);
     }
 
+    /// The seed this graph's draws are made from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Participant names in a stable order, so seeded draws don't depend on hash order
+    fn sorted_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.participants.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     pub fn link_participants(&mut self) {
-        for (name, participant) in &self.participants {
+        let mut rng = fastrand::Rng::with_seed(self.seed);
+        for name in self.sorted_names() {
+            let participant = &self.participants[&name];
             let mut possible_receivers = self
                 .participants
                 .iter()
                 .filter(|(n, p)| {
-                    *n != name
-                        && !participant.exclusions.contains(n)
+                    **n != name
+                        && !participant.excludes(n)
                         && participant
                             .exchange_pools
                             .iter()
@@ -44,8 +66,9 @@ impl ParticipantGraph {
                 })
                 .map(|(n, _)| n.clone())
                 .collect::<Vec<String>>();
-            fastrand::shuffle(&mut possible_receivers);
-            self.edges.insert(name.clone(), possible_receivers);
+            possible_receivers.sort();
+            rng.shuffle(&mut possible_receivers);
+            self.edges.insert(name, possible_receivers);
         }
     }
 
@@ -60,18 +83,39 @@ impl ParticipantGraph {
     /// to find a valid Hamiltonian cycle. If no cycle is found, it falls back
     /// to a simpler pairing strategy.
     pub fn build_exchange(&self) -> Vec<(String, String)> {
+        let mut rng = self.exchange_rng();
+        if let Some(exchange) = self.find_cycle(&mut rng) {
+            return exchange;
+        }
+
+        eprintln!("Warning: Could not find a perfect cycle after 100 attempts. Falling back to best-effort pairing.");
+        self.fallback_exchange(&mut rng)
+    }
+
+    /// Like `build_exchange`, but returns `None` instead of falling back when
+    /// no cycle respects every exclusion, so the caller can relax some and retry
+    pub fn find_exchange(&self) -> Option<Vec<(String, String)>> {
+        self.find_cycle(&mut self.exchange_rng())
+    }
+
+    /// Seeds a separate stream from link_participants' so the two don't mirror each other
+    fn exchange_rng(&self) -> fastrand::Rng {
+        fastrand::Rng::with_seed(self.seed.wrapping_add(1))
+    }
+
+    fn find_cycle(&self, rng: &mut fastrand::Rng) -> Option<Vec<(String, String)>> {
         let num_participants = self.participants.len();
         if num_participants == 0 {
-            return vec![];
+            return Some(vec![]);
         }
 
         // Try multiple times with different random starting points
         for _attempt in 0..100 {
             // Get a random starting participant
-            let mut participants_list: Vec<String> = self.participants.keys().cloned().collect();
-            fastrand::shuffle(&mut participants_list);
+            let mut participants_list = self.sorted_names();
+            rng.shuffle(&mut participants_list);
 
-            if let Some(solution) = self.find_hamiltonian_cycle(&participants_list[0], num_participants) {
+            if let Some(solution) = self.find_hamiltonian_cycle(&participants_list[0], num_participants, rng) {
                 // Convert the cycle to exchange pairs
                 let mut exchange = vec![];
                 for i in 0..solution.len() - 1 {
@@ -79,24 +123,23 @@ impl ParticipantGraph {
                 }
                 // Add the last edge to complete the cycle
                 exchange.push((solution[solution.len() - 1].clone(), solution[0].clone()));
-                return exchange;
+                return Some(exchange);
             }
         }
 
-        eprintln!("Warning: Could not find a perfect cycle after 100 attempts. Falling back to best-effort pairing.");
-        self.fallback_exchange()
+        None
     }
 
     /// Attempts to find a Hamiltonian cycle starting from the given node.
     ///
     /// A Hamiltonian cycle visits each node exactly once and returns to the start.
     /// This ensures everyone gives and receives exactly one gift.
-    fn find_hamiltonian_cycle(&self, start: &str, target_length: usize) -> Option<Vec<String>> {
+    fn find_hamiltonian_cycle(&self, start: &str, target_length: usize, rng: &mut fastrand::Rng) -> Option<Vec<String>> {
         let mut path = vec![start.to_string()];
         let mut visited = HashSet::new();
         visited.insert(start.to_string());
 
-        if self.dfs_hamiltonian(&mut path, &mut visited, target_length, start) {
+        if self.dfs_hamiltonian(&mut path, &mut visited, target_length, start, rng) {
             Some(path)
         } else {
             None
@@ -114,6 +157,7 @@ impl ParticipantGraph {
         visited: &mut HashSet<String>,
         target_length: usize,
         start: &str,
+        rng: &mut fastrand::Rng,
     ) -> bool {
         if path.len() == target_length {
             // Check if we can return to the start
@@ -128,14 +172,14 @@ impl ParticipantGraph {
         if let Some(edges) = self.edges.get(&current) {
             // Try edges in random order
             let mut shuffled_edges = edges.clone();
-            fastrand::shuffle(&mut shuffled_edges);
+            rng.shuffle(&mut shuffled_edges);
 
             for next in shuffled_edges {
                 if !visited.contains(&next) {
                     path.push(next.clone());
                     visited.insert(next.clone());
 
-                    if self.dfs_hamiltonian(path, visited, target_length, start) {
+                    if self.dfs_hamiltonian(path, visited, target_length, start, rng) {
                         return true;
                     }
 
@@ -154,14 +198,14 @@ impl ParticipantGraph {
     /// This creates a simple valid exchange by trying to match givers to receivers
     /// while respecting exclusion rules. If that fails, it falls back to a
     /// simple rotation where each person gives to the next in the list.
-    fn fallback_exchange(&self) -> Vec<(String, String)> {
+    fn fallback_exchange(&self, rng: &mut fastrand::Rng) -> Vec<(String, String)> {
         // Create a simple valid exchange by ensuring everyone gives and receives once
-        let mut givers: Vec<String> = self.participants.keys().cloned().collect();
+        let mut givers = self.sorted_names();
         let mut receivers: Vec<String> = givers.clone();
         let mut exchange = vec![];
 
-        fastrand::shuffle(&mut givers);
-        fastrand::shuffle(&mut receivers);
+        rng.shuffle(&mut givers);
+        rng.shuffle(&mut receivers);
 
         for giver in &givers {
             // Find a valid receiver
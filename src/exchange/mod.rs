@@ -2,4 +2,4 @@ pub mod graph;
 pub mod participant;
 
 pub use graph::ParticipantGraph;
-pub use participant::Participant;
\ No newline at end of file
+pub use participant::{Exclusion, ExclusionReason, Participant};
\ No newline at end of file
@@ -1,19 +1,256 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
 use crate::giftexchange::ExchangePool;
 
 #[derive(Debug, Default, Clone)]
 pub struct Participant {
     pub name: String,
     pub exchange_pools: Vec<ExchangePool>,
-    pub exclusions: Vec<String>,
+    pub exclusions: Vec<Exclusion>,
+    /// Where assignment emails are sent
+    pub email: Option<String>,
 }
 
 impl Participant {
-    pub fn new(name: String, exchange_pools: Vec<ExchangePool>, exclusions: Vec<&str>) -> Participant {
-        let exclusions = exclusions.iter().map(|s| s.to_string()).collect();
+    pub fn new(
+        name: String,
+        exchange_pools: Vec<ExchangePool>,
+        exclusions: Vec<(&str, ExclusionReason)>,
+    ) -> Participant {
+        let exclusions = exclusions
+            .iter()
+            .map(|(name, reason)| Exclusion::always(name, *reason))
+            .collect();
         Participant {
             name,
             exchange_pools,
             exclusions,
+            email: None,
+        }
+    }
+
+    /// Sets where this participant's assignment emails go
+    pub fn with_email(mut self, email: &str) -> Participant {
+        self.email = Some(email.to_string());
+        self
+    }
+
+    /// Adds an exclusion that only applies to some seasons, e.g.
+    /// `Exclusion::until("Noel", ExclusionReason::Request, date)`
+    // Only built for tests until `data::listed_participants` has a time-bounded exclusion
+    #[cfg(test)]
+    pub fn excluding(mut self, exclusion: Exclusion) -> Participant {
+        self.exclusions.push(exclusion);
+        self
+    }
+
+    /// This participant with only the exclusions in force for `season`
+    pub fn for_season(&self, season: i32) -> Participant {
+        Participant {
+            exclusions: self
+                .exclusions
+                .iter()
+                .filter(|e| e.applies_to(season))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// This participant without any exclusions for the given reasons
+    pub fn relaxing(&self, reasons: &[ExclusionReason]) -> Participant {
+        Participant {
+            exclusions: self
+                .exclusions
+                .iter()
+                .filter(|e| !reasons.contains(&e.reason))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether this participant may not give to `name`
+    pub fn excludes(&self, name: &str) -> bool {
+        self.exclusions.iter().any(|e| e.name == name)
+    }
+}
+
+/// Why a participant may not give to someone
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExclusionReason {
+    Spouse,
+    Sibling,
+    /// They live together
+    Household,
+    /// They were paired last year
+    LastYear,
+    /// Someone asked not to be paired
+    #[default]
+    Request,
+}
+
+impl ExclusionReason {
+    /// Every reason, in the order they're relaxed when a pool can't be drawn:
+    /// personal requests and last year's pairings go first and spouses last
+    pub const RELAX_ORDER: [ExclusionReason; 5] = [
+        ExclusionReason::Request,
+        ExclusionReason::LastYear,
+        ExclusionReason::Household,
+        ExclusionReason::Sibling,
+        ExclusionReason::Spouse,
+    ];
+
+    /// The name used on the command line and in storage
+    pub fn slug(&self) -> &'static str {
+        match self {
+            ExclusionReason::Spouse => "spouse",
+            ExclusionReason::Sibling => "sibling",
+            ExclusionReason::Household => "household",
+            ExclusionReason::LastYear => "last-year",
+            ExclusionReason::Request => "request",
+        }
+    }
+
+    pub fn from_slug(slug: &str) -> Result<Self> {
+        Self::RELAX_ORDER
+            .into_iter()
+            .find(|reason| reason.slug() == slug)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid exclusion reason '{slug}'. Valid options are: spouse, sibling, household, last-year, request"
+                )
+            })
+    }
+}
+
+impl Display for ExclusionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            ExclusionReason::Spouse => "spouse",
+            ExclusionReason::Sibling => "sibling",
+            ExclusionReason::Household => "same household",
+            ExclusionReason::LastYear => "last year",
+            ExclusionReason::Request => "personal request",
+        })
+    }
+}
+
+/// Someone a participant may not give to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exclusion {
+    pub name: String,
+    /// Defaults to a personal request for files saved before reasons were recorded
+    #[serde(default)]
+    pub reason: ExclusionReason,
+    /// Seasons the exclusion applies to; empty means every season
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub years: Vec<i32>,
+    /// Last day the exclusion is in force
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+}
+
+impl Exclusion {
+    /// An exclusion that applies every season
+    pub fn always(name: &str, reason: ExclusionReason) -> Exclusion {
+        Exclusion {
+            name: name.to_string(),
+            reason,
+            years: vec![],
+            until: None,
+        }
+    }
+
+    /// An exclusion that only applies to the given seasons
+    #[cfg(test)]
+    pub fn in_years(name: &str, reason: ExclusionReason, years: &[i32]) -> Exclusion {
+        Exclusion {
+            years: years.to_vec(),
+            ..Exclusion::always(name, reason)
         }
     }
-}
\ No newline at end of file
+
+    /// An exclusion that stops applying after `date`
+    #[cfg(test)]
+    pub fn until(name: &str, reason: ExclusionReason, date: NaiveDate) -> Exclusion {
+        Exclusion {
+            until: Some(date),
+            ..Exclusion::always(name, reason)
+        }
+    }
+
+    /// Whether the exclusion is in force for `season`
+    ///
+    /// An expiring exclusion applies to every season whose Christmas falls on
+    /// or before its `until` date.
+    pub fn applies_to(&self, season: i32) -> bool {
+        let in_year = self.years.is_empty() || self.years.contains(&season);
+        let unexpired = match (self.until, NaiveDate::from_ymd_opt(season, 12, 25)) {
+            (Some(until), Some(christmas)) => christmas <= until,
+            _ => true,
+        };
+        in_year && unexpired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn year_list_limits_the_seasons() {
+        let exclusion = Exclusion::in_years("Grant", ExclusionReason::LastYear, &[2024, 2026]);
+        assert!(exclusion.applies_to(2024));
+        assert!(!exclusion.applies_to(2025));
+        assert!(exclusion.applies_to(2026));
+        assert!(Exclusion::always("Grant", ExclusionReason::Spouse).applies_to(1999));
+    }
+
+    #[test]
+    fn until_covers_seasons_whose_christmas_is_on_or_before_it() {
+        let exclusion = Exclusion::until("Noel", ExclusionReason::Household, date(2025, 12, 25));
+        assert!(exclusion.applies_to(2024));
+        assert!(exclusion.applies_to(2025));
+        assert!(!exclusion.applies_to(2026));
+        // Ending before Christmas leaves that season out
+        assert!(!Exclusion::until("Noel", ExclusionReason::Household, date(2025, 12, 24)).applies_to(2025));
+    }
+
+    #[test]
+    fn year_list_and_until_must_both_allow_the_season() {
+        let exclusion = Exclusion {
+            until: Some(date(2025, 12, 31)),
+            ..Exclusion::in_years("Kari", ExclusionReason::Request, &[2023, 2025, 2027])
+        };
+        assert!(exclusion.applies_to(2023));
+        assert!(!exclusion.applies_to(2024));
+        assert!(exclusion.applies_to(2025));
+        assert!(!exclusion.applies_to(2027));
+    }
+
+    #[test]
+    fn for_season_and_relaxing_drop_exclusions() {
+        let claire = Participant::new(
+            "Claire".to_string(),
+            vec![ExchangePool::IslandLife],
+            vec![("Grant", ExclusionReason::Spouse)],
+        )
+        .excluding(Exclusion::in_years("Chris", ExclusionReason::LastYear, &[2025]));
+        assert!(claire.for_season(2025).excludes("Chris"));
+        assert!(!claire.for_season(2026).excludes("Chris"));
+        assert!(claire.for_season(2026).excludes("Grant"));
+
+        let relaxed = claire.for_season(2025).relaxing(&[ExclusionReason::LastYear]);
+        assert!(!relaxed.excludes("Chris"));
+        assert!(relaxed.excludes("Grant"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::exchange::{Exclusion, ExclusionReason, Participant};
+
+/// How one person is related to another
+// Not every relation is recorded in `data::get_relationships` at any one time
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum Relation {
+    Spouse,
+    Partner,
+    /// The person is the other's parent
+    Parent,
+    /// The person is the other's child
+    Child,
+    Sibling,
+}
+
+impl Relation {
+    /// The relation seen from the other person's side
+    pub fn inverse(self) -> Relation {
+        match self {
+            Relation::Parent => Relation::Child,
+            Relation::Child => Relation::Parent,
+            relation => relation,
+        }
+    }
+}
+
+/// `person` is `other`'s `relation`, e.g. Claire is Duncan's spouse
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relationship {
+    pub person: String,
+    pub relation: Relation,
+    pub other: String,
+}
+
+impl Relationship {
+    pub fn new(person: &str, relation: Relation, other: &str) -> Self {
+        Relationship {
+            person: person.to_string(),
+            relation,
+            other: other.to_string(),
+        }
+    }
+
+    /// How `person` is related to `other` according to this relationship, in either direction
+    fn relation_between(&self, person: &str, other: &str) -> Option<Relation> {
+        if self.person == person && self.other == other {
+            Some(self.relation)
+        } else if self.person == other && self.other == person {
+            Some(self.relation.inverse())
+        } else {
+            None
+        }
+    }
+}
+
+/// People related by `relation` may not give to each other, for `reason`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FamilyRule {
+    pub relation: Relation,
+    pub reason: ExclusionReason,
+}
+
+impl FamilyRule {
+    pub fn new(relation: Relation, reason: ExclusionReason) -> Self {
+        FamilyRule { relation, reason }
+    }
+}
+
+/// Adds the exclusions `rules` derive from `relationships` to each participant
+///
+/// A derived exclusion is only skipped when one with the same name and reason
+/// is already listed for every season with no end date, so a listed exclusion
+/// that's limited in time, or is for another reason such as last year's pairing,
+/// doesn't replace a permanent one like a spouse.
+pub fn derive_exclusions(
+    participants: Vec<Participant>,
+    relationships: &[Relationship],
+    rules: &[FamilyRule],
+) -> Vec<Participant> {
+    let names: Vec<String> = participants.iter().map(|p| p.name.clone()).collect();
+    participants
+        .into_iter()
+        .map(|mut participant| {
+            for other in &names {
+                if *other == participant.name {
+                    continue;
+                }
+                let rule = relationships
+                    .iter()
+                    .filter_map(|r| r.relation_between(&participant.name, other))
+                    .find_map(|relation| rules.iter().find(|rule| rule.relation == relation));
+                let Some(rule) = rule else { continue };
+                let listed = participant
+                    .exclusions
+                    .iter()
+                    .any(|e| e.name == *other && e.reason == rule.reason && e.years.is_empty() && e.until.is_none());
+                if !listed {
+                    participant.exclusions.push(Exclusion::always(other, rule.reason));
+                }
+            }
+            participant
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{exchange::ExclusionReason::*, giftexchange::ExchangePool};
+    use chrono::NaiveDate;
+
+    fn derive(alec: Participant) -> Vec<Exclusion> {
+        let participants = vec![
+            alec,
+            Participant::new("Kaylee".to_string(), vec![ExchangePool::IslandLife], vec![]),
+            Participant::new("Will".to_string(), vec![ExchangePool::IslandLife], vec![]),
+        ];
+        let relationships = [
+            Relationship::new("Kaylee", Relation::Spouse, "Alec"),
+            Relationship::new("Will", Relation::Child, "Alec"),
+        ];
+        let rules = [
+            FamilyRule::new(Relation::Spouse, Spouse),
+            FamilyRule::new(Relation::Parent, Household),
+        ];
+        derive_exclusions(participants, &relationships, &rules).remove(0).exclusions
+    }
+
+    fn alec(exclusions: Vec<Exclusion>) -> Participant {
+        exclusions
+            .into_iter()
+            .fold(Participant::new("Alec".to_string(), vec![ExchangePool::IslandLife], vec![]), Participant::excluding)
+    }
+
+    #[test]
+    fn relationships_derive_exclusions_both_ways() {
+        // Will is Alec's child, so Alec is Will's parent
+        assert_eq!(
+            derive(alec(vec![])),
+            vec![Exclusion::always("Kaylee", Spouse), Exclusion::always("Will", Household)]
+        );
+    }
+
+    #[test]
+    fn permanent_listed_exclusions_are_not_repeated() {
+        assert_eq!(
+            derive(alec(vec![Exclusion::always("Kaylee", Spouse)])),
+            vec![Exclusion::always("Kaylee", Spouse), Exclusion::always("Will", Household)]
+        );
+    }
+
+    #[test]
+    fn limited_listed_exclusions_dont_replace_derived_ones() {
+        let in_2024 = Exclusion::in_years("Kaylee", Spouse, &[2024]);
+        let until = Exclusion::until("Will", Household, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap());
+        let last_year = Exclusion::always("Kaylee", LastYear);
+        assert_eq!(
+            derive(alec(vec![in_2024.clone(), until.clone(), last_year.clone()])),
+            vec![
+                in_2024,
+                until,
+                last_year,
+                Exclusion::always("Kaylee", Spouse),
+                Exclusion::always("Will", Household),
+            ]
+        );
+    }
+}
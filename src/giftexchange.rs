@@ -1,12 +1,35 @@
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ExchangePool {
+    #[serde(rename = "island")]
     IslandLife,
+    #[serde(rename = "graber")]
     Grabergishimazureson,
+    #[serde(rename = "pets")]
     Pets,
 }
 
+impl ExchangePool {
+    /// Every pool
+    pub const ALL: [ExchangePool; 3] = [ExchangePool::IslandLife, ExchangePool::Grabergishimazureson, ExchangePool::Pets];
+
+    /// Short identifier used on the command line and in stored history
+    pub fn slug(&self) -> &'static str {
+        match self {
+            ExchangePool::IslandLife => "island",
+            ExchangePool::Grabergishimazureson => "graber",
+            ExchangePool::Pets => "pets",
+        }
+    }
+
+    /// The pool with the given `slug`, if there is one
+    pub fn from_slug(slug: &str) -> Option<ExchangePool> {
+        ExchangePool::ALL.into_iter().find(|pool| pool.slug() == slug)
+    }
+}
+
 impl Display for ExchangePool {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -0,0 +1,140 @@
+use crate::giftexchange::ExchangePool;
+
+/// A pairing from a past (or the current) year's draw
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoricalPairing {
+    pub year: i32,
+    pub pool: ExchangePool,
+    pub giver: String,
+    pub receiver: String,
+}
+
+/// Who a person gave to and received from in one pool in one year
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonYear {
+    pub year: i32,
+    pub pool: ExchangePool,
+    pub gave_to: Option<String>,
+    pub received_from: Option<String>,
+}
+
+/// Returns who `name` gave to and received from, year by year, oldest first
+pub fn person_history(history: &[HistoricalPairing], name: &str) -> Vec<PersonYear> {
+    let mut years: Vec<PersonYear> = vec![];
+    for pairing in history {
+        let is_giver = pairing.giver == name;
+        let is_receiver = pairing.receiver == name;
+        if !is_giver && !is_receiver {
+            continue;
+        }
+
+        let entry = match years
+            .iter_mut()
+            .position(|y| y.year == pairing.year && y.pool == pairing.pool)
+        {
+            Some(idx) => &mut years[idx],
+            None => {
+                years.push(PersonYear {
+                    year: pairing.year,
+                    pool: pairing.pool,
+                    gave_to: None,
+                    received_from: None,
+                });
+                years.last_mut().unwrap()
+            }
+        };
+        if is_giver {
+            entry.gave_to = Some(pairing.receiver.clone());
+        }
+        if is_receiver {
+            entry.received_from = Some(pairing.giver.clone());
+        }
+    }
+    years.sort_by_key(|y| (y.year, y.pool.slug()));
+    years
+}
+
+/// Returns the full pairing list for `pool` in `year`
+pub fn pool_pairings(history: &[HistoricalPairing], pool: ExchangePool, year: i32) -> Vec<(String, String)> {
+    history
+        .iter()
+        .filter(|p| p.pool == pool && p.year == year)
+        .map(|p| (p.giver.clone(), p.receiver.clone()))
+        .collect()
+}
+
+/// Returns how many years before `year` `giver` last drew `receiver`, in any pool
+///
+/// Returns `None` if `giver` has never drawn `receiver`.
+pub fn years_since_drawn(history: &[HistoricalPairing], giver: &str, receiver: &str, year: i32) -> Option<i32> {
+    history
+        .iter()
+        .filter(|p| p.giver == giver && p.receiver == receiver && p.year <= year)
+        .map(|p| year - p.year)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairing(year: i32, pool: ExchangePool, giver: &str, receiver: &str) -> HistoricalPairing {
+        HistoricalPairing {
+            year,
+            pool,
+            giver: giver.to_string(),
+            receiver: receiver.to_string(),
+        }
+    }
+
+    fn history() -> Vec<HistoricalPairing> {
+        vec![
+            pairing(2023, ExchangePool::IslandLife, "Alec", "Will"),
+            pairing(2023, ExchangePool::IslandLife, "Will", "Kaylee"),
+            pairing(2023, ExchangePool::IslandLife, "Kaylee", "Alec"),
+            pairing(2024, ExchangePool::IslandLife, "Will", "Alec"),
+            pairing(2024, ExchangePool::IslandLife, "Alec", "Kaylee"),
+            pairing(2024, ExchangePool::IslandLife, "Kaylee", "Will"),
+            pairing(2024, ExchangePool::Grabergishimazureson, "Alec", "Grant"),
+            pairing(2026, ExchangePool::IslandLife, "Alec", "Will"),
+        ]
+    }
+
+    #[test]
+    fn person_history_joins_giving_and_receiving() {
+        let years = person_history(&history(), "Alec");
+        assert_eq!(
+            years[0],
+            PersonYear {
+                year: 2023,
+                pool: ExchangePool::IslandLife,
+                gave_to: Some("Will".to_string()),
+                received_from: Some("Kaylee".to_string()),
+            }
+        );
+        // Each pool is its own entry, and one Alec only gave in has nobody they received from
+        let grabers = years.iter().find(|y| y.pool == ExchangePool::Grabergishimazureson).unwrap();
+        assert_eq!((grabers.gave_to.as_deref(), grabers.received_from.as_deref()), (Some("Grant"), None));
+        assert_eq!(years.iter().map(|y| y.year).collect::<Vec<_>>(), [2023, 2024, 2024, 2026]);
+        assert_eq!(person_history(&history(), "Grace"), vec![]);
+    }
+
+    #[test]
+    fn pool_pairings_only_has_that_draw() {
+        let pairings = pool_pairings(&history(), ExchangePool::IslandLife, 2024);
+        assert_eq!(
+            pairings,
+            [("Will", "Alec"), ("Alec", "Kaylee"), ("Kaylee", "Will")].map(|(g, r)| (g.to_string(), r.to_string()))
+        );
+        assert_eq!(pool_pairings(&history(), ExchangePool::Pets, 2024), vec![]);
+    }
+
+    #[test]
+    fn years_since_drawn_ignores_later_years() {
+        assert_eq!(years_since_drawn(&history(), "Alec", "Will", 2025), Some(2));
+        assert_eq!(years_since_drawn(&history(), "Alec", "Will", 2026), Some(0));
+        assert_eq!(years_since_drawn(&history(), "Alec", "Kaylee", 2025), Some(1));
+        assert_eq!(years_since_drawn(&history(), "Will", "Kaylee", 2022), None);
+        assert_eq!(years_since_drawn(&history(), "Alec", "Noel", 2025), None);
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::{Context, Result};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    audit::{AuditAction, AuditEntry},
+    giftexchange::ExchangePool,
+    notify::Event,
+    storage::Storage,
+    vault::{self, VaultKey, ADMIN_KEY_ENV},
+};
+
+/// Where an exchange is in its lifecycle
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeStatus {
+    /// The draw can still be regenerated
+    #[default]
+    Draft,
+    /// The draw is locked until an admin unlocks it
+    Finalized,
+}
+
+impl Display for ExchangeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExchangeStatus::Draft => write!(f, "Draft"),
+            ExchangeStatus::Finalized => write!(f, "Finalized"),
+        }
+    }
+}
+
+/// A record of an admin unlocking a finalized draw
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unlock {
+    pub unlocked_by: String,
+    /// RFC 3339 timestamp
+    pub unlocked_at: String,
+    pub reason: String,
+}
+
+/// Fails if the draw for `pool` in `year` has been finalized
+#[cfg(not(target_arch = "wasm32"))]
+pub fn ensure_can_redraw(storage: &dyn Storage, pool: ExchangePool, year: i32) -> Result<()> {
+    if storage.exchange_status(pool, year)? == ExchangeStatus::Finalized {
+        anyhow::bail!(
+            "The {pool} draw for {year} is finalized. An admin must unlock it before it can be regenerated: \
+             cargo run -- unlock {} --reason \"...\"",
+            pool.slug()
+        );
+    }
+    Ok(())
+}
+
+/// Locks the saved draw for `pool` in `year` so it can't be regenerated
+///
+/// Returns the event for the caller to send, since the notifiers can take a
+/// while retrying and the web UI shouldn't wait for them.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn finalize(storage: &mut dyn Storage, pool: ExchangePool, year: i32, finalized_by: &str) -> Result<Event> {
+    if vault::givers(&storage.load_pairings()?, pool, year).is_empty() {
+        anyhow::bail!("There is no saved {pool} draw for {year} to finalize.");
+    }
+    storage.set_exchange_status(pool, year, ExchangeStatus::Finalized)?;
+    storage.append_audit(&AuditEntry::new(finalized_by, AuditAction::Finalize, pool, year))?;
+    Ok(Event::DrawFinalized { pool, year })
+}
+
+/// Unlocks a finalized draw so it can be regenerated, recording who did it and why
+///
+/// Only an admin can: `CHRISTMAS_ADMIN_KEY` must hold the key the draw's pairings were sealed with.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn unlock(storage: &mut dyn Storage, pool: ExchangePool, year: i32, unlocked_by: &str, reason: &str) -> Result<()> {
+    if reason.trim().is_empty() {
+        anyhow::bail!("A reason is required to unlock a finalized draw.");
+    }
+    if storage.exchange_status(pool, year)? != ExchangeStatus::Finalized {
+        anyhow::bail!("The {pool} draw for {year} isn't finalized.");
+    }
+    let key = VaultKey::admin()?;
+    let pairings: Vec<_> = storage
+        .load_pairings()?
+        .into_iter()
+        .filter(|p| p.pool == pool && p.year == year)
+        .collect();
+    vault::decrypt_pairings(&key, &pairings)
+        .with_context(|| format!("{ADMIN_KEY_ENV} isn't the admin key the {pool} draw for {year} was sealed with"))?;
+
+    let unlock = Unlock {
+        unlocked_by: unlocked_by.to_string(),
+        unlocked_at: chrono::Local::now().to_rfc3339(),
+        reason: reason.trim().to_string(),
+    };
+    storage.record_unlock(pool, year, &unlock)?;
+    storage.set_exchange_status(pool, year, ExchangeStatus::Draft)?;
+
+    let mut entry = AuditEntry::new(unlocked_by, AuditAction::Unlock, pool, year);
+    entry.detail = Some(unlock.reason);
+    storage.append_audit(&entry)
+}
@@ -1,52 +1,78 @@
+// The WASM client only uses the shared types from some modules
+#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
+
 use anyhow::Result;
 
-mod data;
-mod exchange;
 mod giftexchange;
+mod history;
+mod lifecycle;
+mod messages;
+mod server;
+mod stats;
+mod themes;
 mod ui;
-mod utils;
-
-use exchange::ParticipantGraph;
-
-
-fn build_exchange() -> Result<()> {
-    // Get CLI args
-    let pool_arg = std::env::args()
-        .nth(1)
-        .expect("No pool specified. Usage: cargo run <island|graber|pets>");
-    
-    let pool = utils::parse_pool_arg(&pool_arg).map_err(anyhow::Error::msg)?;
-
-    // Get participants for the specified pool
-    let participants = data::get_participants_by_pool(pool);
+mod wishlist;
 
-    // Build the graph and generate the exchange
-    let graph = ParticipantGraph::from_participants(participants);
-    let exchange = graph.build_exchange();
-    
-    // Print the exchange pairs
-    println!("\nGift Exchange for {pool}:");
-    println!("==========================");
-    exchange.iter().for_each(|(sender, receiver)| {
-        println!("{sender} -> {receiver}");
-    });
-    
-    // Print the letter for this year
-    let year = utils::current_year();
-    let letter = utils::letter_for_pool(pool);
-    println!("\nLetter for {pool} {year}: {letter}");
-    
-    Ok(())
-}
+// Drawing, storage and the roster stay on the server, out of the WASM client
+#[cfg(not(target_arch = "wasm32"))]
+mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+mod blind;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+mod commitment;
+#[cfg(not(target_arch = "wasm32"))]
+mod data;
+#[cfg(not(target_arch = "wasm32"))]
+mod draw;
+#[cfg(not(target_arch = "wasm32"))]
+mod email;
+#[cfg(not(target_arch = "wasm32"))]
+mod exchange;
+#[cfg(not(target_arch = "wasm32"))]
+mod family;
+#[cfg(not(target_arch = "wasm32"))]
+mod notify;
+#[cfg(not(target_arch = "wasm32"))]
+mod organizer;
+#[cfg(not(target_arch = "wasm32"))]
+mod qr;
+#[cfg(not(target_arch = "wasm32"))]
+mod randomness;
+#[cfg(not(target_arch = "wasm32"))]
+mod reminders;
+#[cfg(not(target_arch = "wasm32"))]
+mod reveal;
+#[cfg(not(target_arch = "wasm32"))]
+mod season;
+#[cfg(not(target_arch = "wasm32"))]
+mod slips;
+#[cfg(not(target_arch = "wasm32"))]
+mod storage;
+#[cfg(test)]
+mod testing;
+#[cfg(not(target_arch = "wasm32"))]
+mod utils;
+#[cfg(not(target_arch = "wasm32"))]
+mod vault;
 
 fn main() -> Result<()> {
     // Check if CLI args are provided
-    if std::env::args().len() > 1 {
-        // Run CLI version
-        build_exchange()?;
-    } else {
-        // Run Dioxus web app
-        dioxus::launch(ui::app);
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if !args.is_empty() {
+            // Run CLI version
+            return cli::run(&args);
+        }
     }
+
+    // Send deadline reminders in the background while the server runs
+    #[cfg(feature = "server")]
+    reminders::start_scheduler();
+
+    // Run Dioxus web app
+    dioxus::launch(ui::app);
     Ok(())
-}
\ No newline at end of file
+}
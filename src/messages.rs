@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+
+use crate::giftexchange::ExchangePool;
+
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::Result;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{reveal, storage::Storage};
+
+/// Longest message that can be posted, in characters
+pub const MAX_MESSAGE_LEN: usize = 1000;
+
+/// Which side of a pairing wrote a message
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageSender {
+    /// The giver, who is never named
+    Santa,
+    Recipient,
+}
+
+/// A message between a giver and their recipient
+///
+/// Each recipient has one Santa per exchange, so the thread is filed under the
+/// recipient alone and the giver's name is never stored with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub recipient: String,
+    pub sender: MessageSender,
+    pub body: String,
+    /// Day it was sent (YYYY-MM-DD), without the time so it can't be matched
+    /// against reveals in the audit log
+    pub sent_on: String,
+}
+
+/// The two threads someone with a reveal link can read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageBoard {
+    /// Who the link's giver gives to
+    pub recipient: String,
+    /// With the person they give to, who doesn't know who they are
+    pub my_recipient: Vec<Message>,
+    /// With whoever gives to them
+    pub my_santa: Vec<Message>,
+}
+
+/// The threads for the giver whose reveal link is `token`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn board(storage: &dyn Storage, token: &str) -> Result<MessageBoard> {
+    let link = reveal::open(storage, token)?;
+    let messages = storage.load_messages(link.pool, link.year)?;
+    let thread = |recipient: &str| -> Vec<Message> {
+        messages.iter().filter(|m| m.recipient == recipient).cloned().collect()
+    };
+    Ok(MessageBoard {
+        my_recipient: thread(&link.receiver),
+        my_santa: thread(&link.giver),
+        recipient: link.receiver,
+    })
+}
+
+/// Posts `body` from the giver whose reveal link is `token` to the person they give to
+#[cfg(not(target_arch = "wasm32"))]
+pub fn message_recipient(storage: &mut dyn Storage, token: &str, body: &str) -> Result<MessageBoard> {
+    let link = open_pairing(storage, token)?;
+    post(storage, link.pool, link.year, &link.receiver, MessageSender::Santa, body)?;
+    board(storage, token)
+}
+
+/// Posts `body` from the giver whose reveal link is `token` to whoever gives to them
+#[cfg(not(target_arch = "wasm32"))]
+pub fn message_santa(storage: &mut dyn Storage, token: &str, body: &str) -> Result<MessageBoard> {
+    let link = open_pairing(storage, token)?;
+    post(storage, link.pool, link.year, &link.giver, MessageSender::Recipient, body)?;
+    board(storage, token)
+}
+
+/// Opens the reveal link `token` to post from, refusing a giver drawn to themselves
+#[cfg(not(target_arch = "wasm32"))]
+fn open_pairing(storage: &dyn Storage, token: &str) -> Result<reveal::Revealed> {
+    let link = reveal::open(storage, token)?;
+    if link.giver == link.receiver {
+        anyhow::bail!("{} was drawn to give to themselves, so there's no one to message.", link.giver);
+    }
+    Ok(link)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn post(
+    storage: &mut dyn Storage,
+    pool: ExchangePool,
+    year: i32,
+    recipient: &str,
+    sender: MessageSender,
+    body: &str,
+) -> Result<()> {
+    let body = body.trim();
+    if body.is_empty() {
+        anyhow::bail!("The message is empty.");
+    }
+    if body.chars().count() > MAX_MESSAGE_LEN {
+        anyhow::bail!("Messages can be at most {MAX_MESSAGE_LEN} characters.");
+    }
+    storage.append_message(&Message {
+        pool,
+        year,
+        recipient: recipient.to_string(),
+        sender,
+        body: body.to_string(),
+        sent_on: chrono::Local::now().format("%Y-%m-%d").to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::JsonStorage,
+        testing::TempPath,
+        vault::{self, VaultKey},
+    };
+
+    /// Storage with `exchange` drawn for Island Life, and each giver's reveal token
+    fn pool(path: &TempPath, exchange: &[(&str, &str)]) -> (JsonStorage, Vec<(String, String)>) {
+        let mut storage = path.storage();
+        let key = VaultKey::generate().unwrap();
+        let exchange: Vec<(String, String)> = exchange.iter().map(|(g, r)| (g.to_string(), r.to_string())).collect();
+        let pairings = vault::encrypt_exchange(&key, ExchangePool::IslandLife, 2024, &exchange).unwrap();
+        storage.save_draw(ExchangePool::IslandLife, 2024, &[], &pairings).unwrap();
+        let tokens = reveal::issue_tokens(&mut storage, &key, ExchangePool::IslandLife, 2024).unwrap();
+        (storage, tokens)
+    }
+
+    fn token<'a>(tokens: &'a [(String, String)], giver: &str) -> &'a str {
+        &tokens.iter().find(|(g, _)| g == giver).unwrap().1
+    }
+
+    const TRIO: [(&str, &str); 3] = [("Alec", "Will"), ("Will", "Grace"), ("Grace", "Alec")];
+
+    #[test]
+    fn threads_go_both_ways() {
+        let path = TempPath::new("messages-threads");
+        let (mut storage, tokens) = pool(&path, &TRIO);
+        message_recipient(&mut storage, token(&tokens, "Alec"), " Any sizes I should know? ").unwrap();
+        let will = message_santa(&mut storage, token(&tokens, "Will"), "Medium, thanks!").unwrap();
+
+        let alec = board(&storage, token(&tokens, "Alec")).unwrap();
+        assert_eq!(alec.recipient, "Will");
+        assert_eq!(alec.my_recipient, will.my_santa);
+        let thread: Vec<_> = alec.my_recipient.iter().map(|m| (m.sender, m.body.as_str())).collect();
+        assert_eq!(
+            thread,
+            [(MessageSender::Santa, "Any sizes I should know?"), (MessageSender::Recipient, "Medium, thanks!")]
+        );
+        assert!(alec.my_santa.is_empty() && will.my_recipient.is_empty());
+    }
+
+    #[test]
+    fn santas_stay_anonymous() {
+        let path = TempPath::new("messages-anonymous");
+        let (mut storage, tokens) = pool(&path, &TRIO);
+        message_recipient(&mut storage, token(&tokens, "Alec"), "Hello from your Santa").unwrap();
+
+        // Neither what's stored nor what Will sees names Alec as his Santa
+        let stored = serde_json::to_string(&storage.load_messages(ExchangePool::IslandLife, 2024).unwrap()).unwrap();
+        assert!(stored.contains("Hello from your Santa") && !stored.contains("Alec"));
+        let will = serde_json::to_string(&board(&storage, token(&tokens, "Will")).unwrap()).unwrap();
+        assert!(will.contains("Hello from your Santa") && !will.contains("Alec"));
+    }
+
+    #[test]
+    fn empty_and_overlong_messages_are_refused() {
+        let path = TempPath::new("messages-length");
+        let (mut storage, tokens) = pool(&path, &TRIO);
+        let alec = token(&tokens, "Alec");
+        assert!(message_recipient(&mut storage, alec, "  \n ").is_err());
+        assert!(message_recipient(&mut storage, alec, &"x".repeat(MAX_MESSAGE_LEN + 1)).is_err());
+        assert!(storage.load_messages(ExchangePool::IslandLife, 2024).unwrap().is_empty());
+        assert!(message_recipient(&mut storage, alec, &"x".repeat(MAX_MESSAGE_LEN)).is_ok());
+    }
+
+    #[test]
+    fn givers_drawn_to_themselves_cant_post() {
+        let path = TempPath::new("messages-self");
+        let (mut storage, tokens) = pool(&path, &[("Alec", "Alec"), ("Will", "Will")]);
+        assert!(message_recipient(&mut storage, token(&tokens, "Alec"), "Hi").is_err());
+        assert!(message_santa(&mut storage, token(&tokens, "Will"), "Hi").is_err());
+        assert!(storage.load_messages(ExchangePool::IslandLife, 2024).unwrap().is_empty());
+    }
+}
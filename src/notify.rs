@@ -0,0 +1,411 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use crate::giftexchange::ExchangePool;
+
+/// Environment variable with the URL notifications are posted to
+pub const WEBHOOK_URL_ENV: &str = "CHRISTMAS_WEBHOOK_URL";
+
+/// Something worth telling the whole family about
+///
+/// Events only carry the exchange and dates, never pairings, so a notification
+/// can go to a group channel whatever its template says.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    DrawFinalized { pool: ExchangePool, year: i32 },
+    DeadlineApproaching {
+        pool: ExchangePool,
+        year: i32,
+        deadline: NaiveDate,
+        days_left: i64,
+    },
+    ExchangeApproaching {
+        pool: ExchangePool,
+        year: i32,
+        date: NaiveDate,
+        days_left: i64,
+    },
+    ExchangeCompleted { pool: ExchangePool, year: i32 },
+}
+
+impl Event {
+    /// The name sent with the event, e.g. in the `X-Christmas-Event` header
+    pub fn slug(&self) -> &'static str {
+        match self {
+            Event::DrawFinalized { .. } => "draw-finalized",
+            Event::DeadlineApproaching { .. } => "deadline-approaching",
+            Event::ExchangeApproaching { .. } => "exchange-approaching",
+            Event::ExchangeCompleted { .. } => "exchange-completed",
+        }
+    }
+
+    pub fn pool(&self) -> ExchangePool {
+        match self {
+            Event::DrawFinalized { pool, .. }
+            | Event::DeadlineApproaching { pool, .. }
+            | Event::ExchangeApproaching { pool, .. }
+            | Event::ExchangeCompleted { pool, .. } => *pool,
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        match self {
+            Event::DrawFinalized { year, .. }
+            | Event::DeadlineApproaching { year, .. }
+            | Event::ExchangeApproaching { year, .. }
+            | Event::ExchangeCompleted { year, .. } => *year,
+        }
+    }
+
+    /// The date a reminder is about and how many days away it is, for reminders
+    pub fn date(&self) -> Option<(NaiveDate, i64)> {
+        match self {
+            Event::DeadlineApproaching { deadline: date, days_left, .. }
+            | Event::ExchangeApproaching { date, days_left, .. } => Some((*date, *days_left)),
+            _ => None,
+        }
+    }
+
+    /// A sentence for the family chat
+    pub fn message(&self) -> String {
+        let (pool, year) = (self.pool(), self.year());
+        let when = match self.date() {
+            Some((_, 0)) => "today".to_string(),
+            Some((_, 1)) => "tomorrow".to_string(),
+            Some((_, days)) => format!("in {days} days"),
+            None => String::new(),
+        };
+        match self {
+            Event::DrawFinalized { .. } => {
+                format!("🎄 The {pool} {year} draw is final! Check your reveal link to see who you're giving to.")
+            }
+            Event::DeadlineApproaching { deadline, .. } => {
+                format!(
+                    "⏰ {pool} {year} gifts should be bought {when}, by {}.",
+                    deadline.format("%A, %B %-d")
+                )
+            }
+            Event::ExchangeApproaching { date, .. } => {
+                format!("🎁 The {pool} {year} exchange is {when}, on {}.", date.format("%A, %B %-d"))
+            }
+            Event::ExchangeCompleted { .. } => format!("🎁 The {pool} {year} exchange is done. Merry Christmas!"),
+        }
+    }
+}
+
+/// Somewhere events are sent
+pub trait Notifier {
+    fn notify(&self, event: &Event) -> Result<()>;
+}
+
+/// Every notifier configured in the environment
+pub fn notifiers() -> Result<Vec<Box<dyn Notifier>>> {
+    #[cfg(feature = "webhook")]
+    return Ok(WebhookNotifier::from_env()?
+        .into_iter()
+        .map(|webhook| Box::new(webhook) as Box<dyn Notifier>)
+        .collect());
+
+    #[cfg(not(feature = "webhook"))]
+    {
+        if std::env::var(WEBHOOK_URL_ENV).is_ok_and(|url| !url.is_empty()) {
+            anyhow::bail!("{WEBHOOK_URL_ENV} is set, but posting to webhooks needs the webhook feature");
+        }
+        Ok(vec![])
+    }
+}
+
+/// Sends `event` to every configured notifier, returning how many it reached
+pub fn send(event: &Event) -> Result<usize> {
+    let notifiers = notifiers()?;
+    for notifier in &notifiers {
+        notifier.notify(event)?;
+    }
+    Ok(notifiers.len())
+}
+
+/// Sends `event`, warning rather than failing if it can't be delivered, for
+/// actions that shouldn't be undone by a chat being down
+pub fn send_or_warn(event: &Event) {
+    if let Err(e) = send(event) {
+        eprintln!("Warning: Failed to send the {} notification: {e:#}", event.slug());
+    }
+}
+
+#[cfg(feature = "webhook")]
+pub use webhook::WebhookNotifier;
+
+#[cfg(feature = "webhook")]
+mod webhook {
+    use anyhow::{Context, Result};
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use super::{Event, Notifier, WEBHOOK_URL_ENV};
+    use crate::{audit::to_hex, utils};
+
+    /// Environment variable with the path of the webhook payload template
+    pub const WEBHOOK_TEMPLATE_ENV: &str = "CHRISTMAS_WEBHOOK_TEMPLATE";
+
+    /// Environment variable with the secret webhook payloads are signed with
+    pub const WEBHOOK_SECRET_ENV: &str = "CHRISTMAS_WEBHOOK_SECRET";
+
+    /// Environment variable with how many times a failed delivery is retried
+    pub const WEBHOOK_RETRIES_ENV: &str = "CHRISTMAS_WEBHOOK_RETRIES";
+
+    /// Payload used when `CHRISTMAS_WEBHOOK_TEMPLATE` isn't set, which chat
+    /// services such as Slack and Mattermost accept as is
+    const DEFAULT_TEMPLATE: &str =
+        r#"{"text": "{message}", "event": "{event}", "pool": "{pool}", "year": {year}}"#;
+
+    const DEFAULT_RETRIES: u32 = 3;
+
+    /// Posts events to a URL, rendering the body from a template
+    ///
+    /// The template can use `{event}`, `{message}`, `{pool}`, `{pool_slug}`,
+    /// `{year}`, `{deadline}` and `{days_left}`, filled in as JSON string
+    /// contents; for reminders, `{deadline}` is the date the reminder is about.
+    /// With a secret, each request is signed.
+    pub struct WebhookNotifier {
+        pub url: String,
+        pub template: String,
+        pub secret: Option<String>,
+        /// Attempts after the first before giving up
+        pub retries: u32,
+    }
+
+    impl WebhookNotifier {
+        /// The webhook configured by `CHRISTMAS_WEBHOOK_*`, or `None` if there's no URL
+        pub fn from_env() -> Result<Option<Self>> {
+            let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+            let Some(url) = var(WEBHOOK_URL_ENV) else {
+                return Ok(None);
+            };
+            let template = match var(WEBHOOK_TEMPLATE_ENV) {
+                Some(path) => std::fs::read_to_string(&path).with_context(|| format!("Failed to read the webhook template {path}"))?,
+                None => DEFAULT_TEMPLATE.to_string(),
+            };
+            let retries = match var(WEBHOOK_RETRIES_ENV) {
+                Some(retries) => retries
+                    .parse()
+                    .with_context(|| format!("{WEBHOOK_RETRIES_ENV} must be a whole number"))?,
+                None => DEFAULT_RETRIES,
+            };
+            Ok(Some(WebhookNotifier {
+                url,
+                template,
+                secret: var(WEBHOOK_SECRET_ENV),
+                retries,
+            }))
+        }
+
+        /// The request body for `event`
+        pub fn payload(&self, event: &Event) -> Result<String> {
+            let (deadline, days_left) = match event.date() {
+                Some((date, days_left)) => (date.to_string(), days_left.to_string()),
+                None => (String::new(), String::new()),
+            };
+            fill_json(
+                &self.template,
+                &[
+                    ("event", event.slug().to_string()),
+                    ("message", event.message()),
+                    ("pool", event.pool().to_string()),
+                    ("pool_slug", event.pool().slug().to_string()),
+                    ("year", event.year().to_string()),
+                    ("deadline", deadline),
+                    ("days_left", days_left),
+                ],
+            )
+        }
+
+        /// The body and headers to post for `event` at `timestamp`, signed if there's a secret
+        ///
+        /// `X-Christmas-Signature` is `sha256=` and the hex HMAC-SHA256 of
+        /// `<X-Christmas-Timestamp>.<body>`, so receivers can check it came
+        /// from us and isn't a replay.
+        fn request(&self, event: &Event, timestamp: &str) -> Result<WebhookRequest> {
+            let body = self.payload(event)?;
+            let signature = match &self.secret {
+                Some(secret) => {
+                    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).context("Invalid webhook secret")?;
+                    mac.update(format!("{timestamp}.{body}").as_bytes());
+                    Some(format!("sha256={}", to_hex(&mac.finalize().into_bytes())))
+                }
+                None => None,
+            };
+            Ok(WebhookRequest {
+                body,
+                timestamp: timestamp.to_string(),
+                signature,
+            })
+        }
+
+        /// Posts `request` once
+        fn post(&self, agent: &ureq::Agent, event: &Event, request: &WebhookRequest) -> Result<(), Failure> {
+            let mut post = agent
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .header("X-Christmas-Event", event.slug())
+                .header("X-Christmas-Timestamp", &request.timestamp);
+            if let Some(signature) = &request.signature {
+                post = post.header("X-Christmas-Signature", signature);
+            }
+            match post.send(&request.body) {
+                Ok(_) => Ok(()),
+                Err(ureq::Error::StatusCode(status)) if status != 429 && status < 500 => Err(Failure::Refused(
+                    anyhow::anyhow!("The webhook refused the {} notification with HTTP {status}", event.slug()),
+                )),
+                Err(e) => Err(Failure::Transient(e.into())),
+            }
+        }
+    }
+
+    /// A webhook post, ready to send
+    struct WebhookRequest {
+        body: String,
+        timestamp: String,
+        signature: Option<String>,
+    }
+
+    /// Why posting a notification failed
+    enum Failure {
+        /// Worth retrying: a network error, a 429 or a 5xx
+        Transient(anyhow::Error),
+        /// Any other refusal, which retrying won't fix
+        Refused(anyhow::Error),
+    }
+
+    /// Fills `template` with `values` escaped as JSON string contents
+    fn fill_json(template: &str, values: &[(&str, String)]) -> Result<String> {
+        let values: Vec<(&str, String)> = values.iter().map(|(name, value)| (*name, json_escape(value))).collect();
+        utils::fill_template(template, "webhook template", &values)
+    }
+
+    /// `value` escaped to go between the quotes of a JSON string
+    fn json_escape(value: &str) -> String {
+        let quoted = serde_json::to_string(value).expect("Strings always serialize");
+        quoted[1..quoted.len() - 1].to_string()
+    }
+
+    /// Runs `attempt` until it succeeds, is refused, or has been retried
+    /// `retries` times, calling `wait` with the number of retries so far before each one
+    fn with_retries(
+        slug: &str,
+        retries: u32,
+        mut wait: impl FnMut(u32),
+        mut attempt: impl FnMut() -> Result<(), Failure>,
+    ) -> Result<()> {
+        let mut retried = 0;
+        loop {
+            let error = match attempt() {
+                Ok(()) => return Ok(()),
+                Err(Failure::Refused(error)) => return Err(error),
+                Err(Failure::Transient(error)) => error,
+            };
+            if retried >= retries {
+                return Err(error)
+                    .with_context(|| format!("Failed to post the {slug} notification after {} attempts", retried + 1));
+            }
+            wait(retried);
+            retried += 1;
+        }
+    }
+
+    impl Notifier for WebhookNotifier {
+        /// Posts the payload, retrying with backoff on network errors, 429s and 5xxs
+        fn notify(&self, event: &Event) -> Result<()> {
+            let request = self.request(event, &chrono::Utc::now().timestamp().to_string())?;
+            let agent: ureq::Agent = ureq::Agent::config_builder()
+                .timeout_global(Some(std::time::Duration::from_secs(10)))
+                .build()
+                .into();
+            with_retries(
+                event.slug(),
+                self.retries,
+                |retried| std::thread::sleep(std::time::Duration::from_secs(1 << retried.min(5))),
+                || self.post(&agent, event, &request),
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::giftexchange::ExchangePool;
+
+        fn webhook(secret: Option<&str>) -> WebhookNotifier {
+            WebhookNotifier {
+                url: "http://localhost:9/hook".to_string(),
+                template: DEFAULT_TEMPLATE.to_string(),
+                secret: secret.map(str::to_string),
+                retries: 2,
+            }
+        }
+
+        fn finalized() -> Event {
+            Event::DrawFinalized {
+                pool: ExchangePool::IslandLife,
+                year: 2025,
+            }
+        }
+
+        #[test]
+        fn default_payload_escapes_quotes_and_newlines() {
+            let message = "Claire said \"wrap it\"\nand \\ left";
+            let values = [
+                ("message", message.to_string()),
+                ("event", "draw-finalized".to_string()),
+                ("pool", "Pets".to_string()),
+                ("year", "2025".to_string()),
+            ];
+            let json: serde_json::Value = serde_json::from_str(&fill_json(DEFAULT_TEMPLATE, &values).unwrap()).unwrap();
+            assert_eq!(json["text"], message);
+            assert_eq!(json["year"], 2025);
+
+            let json: serde_json::Value = serde_json::from_str(&webhook(None).payload(&finalized()).unwrap()).unwrap();
+            assert_eq!(json["text"], finalized().message());
+            assert_eq!(json["event"], "draw-finalized");
+        }
+
+        #[test]
+        fn signature_is_the_hmac_of_the_body_sent() {
+            let request = webhook(Some("s3cret")).request(&finalized(), "1735000000").unwrap();
+            assert_eq!(request.body, webhook(None).payload(&finalized()).unwrap());
+            let signature = request.signature.unwrap();
+            let hex = signature.strip_prefix("sha256=").unwrap();
+            let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+            mac.update(format!("1735000000.{}", request.body).as_bytes());
+            assert_eq!(hex, to_hex(&mac.finalize().into_bytes()));
+
+            assert!(webhook(None).request(&finalized(), "1735000000").unwrap().signature.is_none());
+        }
+
+        #[test]
+        fn transient_failures_are_retried_up_to_the_limit() {
+            let (mut attempts, mut waits) = (0, vec![]);
+            let result = with_retries("test", 2, |retried| waits.push(retried), || {
+                attempts += 1;
+                Err(Failure::Transient(anyhow::anyhow!("connection refused")))
+            });
+            assert!(result.unwrap_err().to_string().contains("after 3 attempts"));
+            assert_eq!((attempts, waits), (3, vec![0, 1]));
+
+            let mut attempts = 0;
+            let result = with_retries("test", 2, |_| {}, || {
+                attempts += 1;
+                if attempts < 2 { Err(Failure::Transient(anyhow::anyhow!("HTTP 503"))) } else { Ok(()) }
+            });
+            assert!(result.is_ok());
+            assert_eq!(attempts, 2);
+
+            let mut attempts = 0;
+            let result = with_retries("test", 2, |_| {}, || {
+                attempts += 1;
+                Err(Failure::Refused(anyhow::anyhow!("HTTP 404")))
+            });
+            assert!(result.is_err());
+            assert_eq!(attempts, 1);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::audit::to_hex;
+
+/// Environment variable listing who can sign in to the web UI as an organizer,
+/// as comma-separated `name:hash` entries from `cargo run -- organizer-key <name>`
+pub const ORGANIZERS_ENV: &str = "CHRISTMAS_ORGANIZERS";
+
+/// A new sign-in key for the organizer `name`, and their `name:hash` entry for `CHRISTMAS_ORGANIZERS`
+///
+/// Only the key's hash goes in the server's environment, so reading it doesn't let anyone sign in.
+pub fn new_key(name: &str) -> Result<(String, String)> {
+    let name = name.trim();
+    if name.is_empty() || name.contains([':', ',']) {
+        anyhow::bail!("Organizer names can't be empty or contain ':' or ','.");
+    }
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).context("Failed to generate a sign-in key")?;
+    let key = to_hex(&bytes);
+    let entry = format!("{name}:{}", hash_key(&key));
+    Ok((key, entry))
+}
+
+fn hash_key(key: &str) -> String {
+    to_hex(&Sha256::digest(format!("christmas organizer key:{key}")))
+}
+
+/// The name of the organizer whose sign-in key is `key`, from `CHRISTMAS_ORGANIZERS`
+#[cfg(feature = "server")]
+pub fn authenticate(key: &str) -> Result<String> {
+    let organizers = std::env::var(ORGANIZERS_ENV).with_context(|| {
+        format!("Nobody can sign in: set {ORGANIZERS_ENV} with entries from `cargo run -- organizer-key <name>`.")
+    })?;
+    let hash = hash_key(key.trim());
+    organizers
+        .split(',')
+        .filter_map(|entry| entry.trim().split_once(':'))
+        .find(|(_, entry_hash)| *entry_hash == hash)
+        .map(|(name, _)| name.to_string())
+        .context("That isn't an organizer's sign-in key.")
+}
+
+#[cfg(feature = "server")]
+pub use session::{end_session, session_organizer, start_session, SESSION_LENGTH};
+
+/// Signed-in organizers, kept in memory so restarting the server signs everyone out
+#[cfg(feature = "server")]
+mod session {
+    use anyhow::{Context, Result};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use crate::audit::to_hex;
+
+    /// How long a sign-in lasts
+    pub const SESSION_LENGTH: Duration = Duration::from_secs(12 * 60 * 60);
+
+    struct Session {
+        /// Hash of the session token, so the token itself is only ever in the organizer's cookie
+        token_hash: String,
+        organizer: String,
+        expires: Instant,
+    }
+
+    static SESSIONS: Mutex<Vec<Session>> = Mutex::new(vec![]);
+
+    /// Signs `organizer` in, returning the token that identifies their session
+    pub fn start_session(organizer: &str) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes).context("Failed to generate a session token")?;
+        let token = to_hex(&bytes);
+        let mut sessions = SESSIONS.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|session| session.expires > Instant::now());
+        sessions.push(Session {
+            token_hash: super::hash_key(&token),
+            organizer: organizer.to_string(),
+            expires: Instant::now() + SESSION_LENGTH,
+        });
+        Ok(token)
+    }
+
+    /// The organizer signed in with the session `token`, if it hasn't expired
+    pub fn session_organizer(token: &str) -> Option<String> {
+        let hash = super::hash_key(token);
+        SESSIONS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|session| session.token_hash == hash && session.expires > Instant::now())
+            .map(|session| session.organizer.clone())
+    }
+
+    /// Signs out the session `token`
+    pub fn end_session(token: &str) {
+        let hash = super::hash_key(token);
+        SESSIONS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|session| session.token_hash != hash);
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use qrcode::{render::svg, EcLevel, QrCode};
+
+/// Smallest width and height of a rendered code, in SVG units or PNG pixels
+const MIN_SIZE: u32 = 200;
+
+/// An SVG QR code for `url`, ready to inline in an HTML page
+///
+/// Codes are drawn here rather than by an online QR service, so reveal links
+/// never leave the machine.
+pub fn svg(url: &str) -> Result<String> {
+    let svg = code(url)?
+        .render::<svg::Color>()
+        .min_dimensions(MIN_SIZE, MIN_SIZE)
+        .build();
+    // The XML declaration is only allowed in a standalone file
+    Ok(match svg.find("<svg") {
+        Some(start) => svg[start..].to_string(),
+        None => svg,
+    })
+}
+
+/// A PNG QR code for `url`, for attaching to emails
+pub fn png(url: &str) -> Result<Vec<u8>> {
+    let image = code(url)?
+        .render::<image::Luma<u8>>()
+        .min_dimensions(MIN_SIZE, MIN_SIZE)
+        .build();
+    let mut bytes = std::io::Cursor::new(vec![]);
+    image
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .context("Failed to encode a QR code as a PNG")?;
+    Ok(bytes.into_inner())
+}
+
+fn code(url: &str) -> Result<QrCode> {
+    QrCode::with_error_correction_level(url, EcLevel::M).context("Failed to make a QR code for a reveal link")
+}
@@ -0,0 +1,449 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Display;
+
+use crate::{
+    audit::to_hex,
+    data,
+    giftexchange::ExchangePool,
+    storage::Storage,
+    vault::VaultKey,
+};
+
+/// Name the organizer's own contribution is recorded under
+///
+/// Revealed values are public, so a seed made only from them would let anyone
+/// who knows the roster redo the draw. The organizer's value is committed to
+/// before anyone reveals, so it can't be chosen to steer the draw, but it stays
+/// encrypted under the admin key until the draw's opening is published.
+pub const ORGANIZER: &str = "(organizer)";
+
+/// Where a participant's contribution to the joint seed stands
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContributionStatus {
+    /// Committed, value not revealed yet
+    Committed,
+    Revealed,
+    /// Revealed and mixed into the seed of a draw
+    Used,
+    /// Not revealed before the draw, so left out of the seed
+    Forfeited,
+}
+
+impl Display for ContributionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            ContributionStatus::Committed => "committed",
+            ContributionStatus::Revealed => "revealed",
+            ContributionStatus::Used => "used",
+            ContributionStatus::Forfeited => "forfeited",
+        })
+    }
+}
+
+/// One participant's share of the randomness behind a draw
+///
+/// The participant first submits only `commitment`, a hash of a random value
+/// they keep to themselves. Once everyone has committed they reveal the value,
+/// and the revealed values together seed the draw. Nobody can pick their value
+/// after seeing anyone else's, so no single person controls the outcome.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeedContribution {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub participant: String,
+    pub commitment: String,
+    pub value: Option<String>,
+    pub status: ContributionStatus,
+    /// RFC 3339 timestamps
+    pub committed_at: String,
+    pub revealed_at: Option<String>,
+    /// Last day reveals are accepted (YYYY-MM-DD), recorded on the organizer's
+    /// contribution when the round is opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reveal_by: Option<String>,
+}
+
+/// A new random value for `participant` to contribute, and its commitment
+pub fn new_value(pool: ExchangePool, year: i32, participant: &str) -> Result<(String, String)> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).context("Failed to generate a random value")?;
+    let value = to_hex(&bytes);
+    let commitment = commit(pool, year, participant, &value);
+    Ok((value, commitment))
+}
+
+/// Hex SHA-256 commitment to `value`, bound to the exchange and participant so
+/// it can't be copied by someone else
+pub fn commit(pool: ExchangePool, year: i32, participant: &str, value: &str) -> String {
+    to_hex(&Sha256::digest(format!(
+        "christmas seed commitment v1\n{}\n{year}\n{participant}\n{}",
+        pool.slug(),
+        value.trim()
+    )))
+}
+
+/// The last day reveals are accepted for `contributions`, if the round has a deadline
+///
+/// Rounds started before deadlines were recorded have none.
+pub fn reveal_deadline(contributions: &[SeedContribution]) -> Result<Option<NaiveDate>> {
+    contributions
+        .iter()
+        .find(|c| c.participant == ORGANIZER)
+        .and_then(|c| c.reveal_by.as_deref())
+        .map(|date| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").with_context(|| format!("Invalid reveal deadline '{date}'"))
+        })
+        .transpose()
+}
+
+/// Opens the joint seed round for the draw of `pool` in `year`, committing the
+/// organizer's value, encrypted under `key`
+///
+/// `reveal_by` is the last day reveals are accepted. It's fixed now, before
+/// anyone commits, and the draw has to wait until it has passed.
+pub fn open_round(
+    storage: &mut dyn Storage,
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+    reveal_by: NaiveDate,
+    today: NaiveDate,
+) -> Result<()> {
+    let contributions = storage.load_seed_contributions(pool, year)?;
+    if !contributions.is_empty() {
+        anyhow::bail!("The {pool} {year} seed round is already open.");
+    }
+    if reveal_by < today {
+        anyhow::bail!("The reveal deadline {reveal_by} has already passed.");
+    }
+    let (value, commitment) = new_value(pool, year, ORGANIZER)?;
+    let organizer = SeedContribution {
+        pool,
+        year,
+        participant: ORGANIZER.to_string(),
+        commitment,
+        value: Some(key.encrypt(&value, &organizer_context(pool, year))?),
+        status: ContributionStatus::Committed,
+        committed_at: chrono::Local::now().to_rfc3339(),
+        revealed_at: None,
+        reveal_by: Some(reveal_by.format("%Y-%m-%d").to_string()),
+    };
+    storage.save_seed_contributions(pool, year, &[organizer])
+}
+
+/// Records `participant`'s commitment for the draw of `pool` in `year`
+///
+/// Commitments close once anyone reveals, so a latecomer can't choose their
+/// value after seeing others, and at the reveal deadline. Committing again
+/// before then replaces the earlier commitment.
+pub fn record_commitment(
+    storage: &mut dyn Storage,
+    pool: ExchangePool,
+    year: i32,
+    participant: &str,
+    commitment: &str,
+    today: NaiveDate,
+) -> Result<()> {
+    let commitment = commitment.trim().to_lowercase();
+    if commitment.len() != 64 || !commitment.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("A commitment is 64 hex digits, as printed by `cargo run -- seed value`.");
+    }
+    if !data::get_participants_by_pool(pool).iter().any(|p| p.name == participant) {
+        anyhow::bail!("{participant} isn't in the {pool} exchange.");
+    }
+    let mut contributions = storage.load_seed_contributions(pool, year)?;
+    if contributions.is_empty() {
+        anyhow::bail!(
+            "The {pool} {year} seed round isn't open. Open it with `cargo run -- seed open {} <reveal-by YYYY-MM-DD>`.",
+            pool.slug()
+        );
+    }
+    if let Some(deadline) = reveal_deadline(&contributions)?.filter(|deadline| today > *deadline) {
+        anyhow::bail!("Commitments for the {pool} {year} draw are closed: reveals closed at the end of {deadline}.");
+    }
+    if let Some(closed) = contributions.iter().find(|c| c.status != ContributionStatus::Committed) {
+        anyhow::bail!(
+            "Commitments for the {pool} {year} draw are closed: {} has already {}.",
+            closed.participant,
+            closed.status
+        );
+    }
+
+    contributions.retain(|c| c.participant != participant);
+    contributions.push(SeedContribution {
+        pool,
+        year,
+        participant: participant.to_string(),
+        commitment,
+        value: None,
+        status: ContributionStatus::Committed,
+        committed_at: chrono::Local::now().to_rfc3339(),
+        revealed_at: None,
+        reveal_by: None,
+    });
+    storage.save_seed_contributions(pool, year, &contributions)
+}
+
+/// Records `participant`'s revealed value, which must match their commitment
+///
+/// Reveals are refused after the reveal deadline, or once a draw has used the
+/// seed; a participant who hadn't revealed by then has forfeited their contribution.
+pub fn record_reveal(
+    storage: &mut dyn Storage,
+    pool: ExchangePool,
+    year: i32,
+    participant: &str,
+    value: &str,
+    today: NaiveDate,
+) -> Result<()> {
+    let mut contributions = storage.load_seed_contributions(pool, year)?;
+    if contributions
+        .iter()
+        .any(|c| matches!(c.status, ContributionStatus::Used | ContributionStatus::Forfeited))
+    {
+        anyhow::bail!("Too late: the {pool} {year} draw has already been seeded, so this reveal can't count.");
+    }
+    if let Some(deadline) = reveal_deadline(&contributions)?.filter(|deadline| today > *deadline) {
+        anyhow::bail!("Too late: reveals for the {pool} {year} draw closed at the end of {deadline}.");
+    }
+    if participant == ORGANIZER {
+        anyhow::bail!("The organizer's value is revealed by the draw itself.");
+    }
+    let contribution = contributions
+        .iter_mut()
+        .find(|c| c.participant == participant)
+        .with_context(|| format!("{participant} hasn't committed to the {pool} {year} draw."))?;
+    if commit(pool, year, participant, value) != contribution.commitment {
+        anyhow::bail!("That value doesn't match {participant}'s commitment.");
+    }
+
+    contribution.value = Some(value.trim().to_string());
+    contribution.status = ContributionStatus::Revealed;
+    contribution.revealed_at = Some(chrono::Local::now().to_rfc3339());
+    storage.save_seed_contributions(pool, year, &contributions)
+}
+
+/// What the organizer's value is encrypted against
+fn organizer_context(pool: ExchangePool, year: i32) -> String {
+    format!("seed-contribution/{}/{year}", pool.slug())
+}
+
+/// The seed made from the values of `contributions` marked used
+///
+/// Hashing every value together means changing any one of them changes the
+/// whole seed.
+pub fn combine(contributions: &[SeedContribution]) -> u64 {
+    let mut lines: Vec<String> = contributions
+        .iter()
+        .filter(|c| c.status == ContributionStatus::Used)
+        .filter_map(|c| c.value.as_ref().map(|value| format!("{} {value}\n", c.participant)))
+        .collect();
+    lines.sort();
+    let digest = Sha256::digest(format!("christmas joint seed v1\n{}", lines.concat()));
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 is longer than 8 bytes"))
+}
+
+/// The joint seed for the draw of `pool` in `year`, or `None` if nobody has committed
+///
+/// The first draw closes the round: revealed contributions and the
+/// organizer's are marked used, and unrevealed ones forfeited. It's refused
+/// until the reveal deadline has passed, so the organizer, who can read their
+/// own value, can't pick the moment to draw by watching the reveals come in.
+/// Redraws reuse the same contributions, so regenerating can't re-roll the
+/// seed. The returned contributions include the organizer's value decrypted
+/// with `key`, for the draw's opening.
+pub fn seed_for_draw(
+    storage: &mut dyn Storage,
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+    today: NaiveDate,
+) -> Result<Option<(u64, Vec<SeedContribution>)>> {
+    let mut contributions = storage.load_seed_contributions(pool, year)?;
+    if contributions.is_empty() {
+        return Ok(None);
+    }
+
+    let open = contributions
+        .iter()
+        .all(|c| matches!(c.status, ContributionStatus::Committed | ContributionStatus::Revealed));
+    if open {
+        if let Some(deadline) = reveal_deadline(&contributions)?.filter(|deadline| today <= *deadline) {
+            anyhow::bail!("Reveals for the {pool} {year} draw are open until the end of {deadline}, so it can't be drawn yet.");
+        }
+        if !contributions.iter().any(|c| c.status == ContributionStatus::Revealed) {
+            anyhow::bail!("Nobody has revealed their value for the {pool} {year} draw yet, so it can't be seeded.");
+        }
+        for contribution in &mut contributions {
+            contribution.status = match contribution.status {
+                _ if contribution.participant == ORGANIZER => ContributionStatus::Used,
+                ContributionStatus::Revealed => ContributionStatus::Used,
+                _ => ContributionStatus::Forfeited,
+            };
+        }
+        storage.save_seed_contributions(pool, year, &contributions)?;
+    }
+
+    for contribution in contributions.iter_mut().filter(|c| c.participant == ORGANIZER) {
+        let encrypted = contribution.value.as_deref().unwrap_or_default();
+        contribution.value = Some(key.decrypt(encrypted, &organizer_context(pool, year))?);
+    }
+    Ok(Some((combine(&contributions), contributions)))
+}
+
+/// Checks that every used contribution matches its commitment and that they
+/// combine to `seed`
+pub fn verify(contributions: &[SeedContribution], seed: u64) -> Result<()> {
+    let used: Vec<SeedContribution> = contributions
+        .iter()
+        .filter(|c| c.status == ContributionStatus::Used)
+        .cloned()
+        .collect();
+    if used.is_empty() {
+        anyhow::bail!("No contribution was used, so the draw wasn't jointly seeded.");
+    }
+    for contribution in &used {
+        let value = contribution.value.as_deref().unwrap_or_default();
+        if commit(contribution.pool, contribution.year, &contribution.participant, value) != contribution.commitment {
+            anyhow::bail!("{}'s revealed value doesn't match their commitment.", contribution.participant);
+        }
+    }
+    if combine(&used) != seed {
+        anyhow::bail!("The revealed values don't combine to the draw's seed.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage::JsonStorage, testing::TempPath};
+
+    const POOL: ExchangePool = ExchangePool::IslandLife;
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    /// A round opened on Dec 1st taking reveals until Dec 5th, with Claire and Grant committed
+    fn open_with_commitments(name: &str) -> (JsonStorage, TempPath, VaultKey, [(String, String); 2]) {
+        let path = TempPath::new(&format!("seed-{name}"));
+        let mut storage = path.storage();
+        let key = VaultKey::generate().unwrap();
+        open_round(&mut storage, &key, POOL, 2025, day("2025-12-05"), day("2025-12-01")).unwrap();
+        let values = ["Claire", "Grant"].map(|name| {
+            let (value, commitment) = new_value(POOL, 2025, name).unwrap();
+            record_commitment(&mut storage, POOL, 2025, name, &commitment, day("2025-12-01")).unwrap();
+            (name.to_string(), value)
+        });
+        (storage, path, key, values)
+    }
+
+    fn used(participant: &str, value: &str) -> SeedContribution {
+        SeedContribution {
+            pool: POOL,
+            year: 2025,
+            participant: participant.to_string(),
+            commitment: commit(POOL, 2025, participant, value),
+            value: Some(value.to_string()),
+            status: ContributionStatus::Used,
+            committed_at: "2025-12-01T00:00:00+00:00".to_string(),
+            revealed_at: None,
+            reveal_by: None,
+        }
+    }
+
+    #[test]
+    fn combine_ignores_order_and_unused_contributions() {
+        let (claire, grant) = (used("Claire", "aa"), used("Grant", "bb"));
+        let seed = combine(&[claire.clone(), grant.clone()]);
+        assert_eq!(seed, combine(&[grant.clone(), claire.clone()]));
+
+        let forfeited = SeedContribution {
+            status: ContributionStatus::Forfeited,
+            ..used("Anne", "cc")
+        };
+        assert_eq!(seed, combine(&[claire.clone(), grant.clone(), forfeited]));
+        assert_ne!(seed, combine(&[claire.clone(), used("Grant", "bc")]));
+        assert_ne!(seed, combine(&[claire]));
+    }
+
+    #[test]
+    fn verify_checks_values_and_seed() {
+        let contributions = [used("Claire", "aa"), used("Grant", "bb")];
+        let seed = combine(&contributions);
+        verify(&contributions, seed).unwrap();
+        assert!(verify(&contributions, seed ^ 1).is_err());
+
+        // A value swapped after committing no longer matches the commitment
+        let swapped = SeedContribution {
+            value: Some("cc".to_string()),
+            ..used("Grant", "bb")
+        };
+        let contributions = [used("Claire", "aa"), swapped];
+        assert!(verify(&contributions, combine(&contributions)).is_err());
+
+        let forfeited = [SeedContribution {
+            status: ContributionStatus::Forfeited,
+            ..used("Claire", "aa")
+        }];
+        assert!(verify(&forfeited, combine(&forfeited)).is_err());
+    }
+
+    #[test]
+    fn draw_waits_for_the_reveal_deadline() {
+        let (mut storage, _path, key, [(claire, value), _]) = open_with_commitments("deadline");
+        record_reveal(&mut storage, POOL, 2025, &claire, &value, day("2025-12-02")).unwrap();
+        assert!(seed_for_draw(&mut storage, &key, POOL, 2025, day("2025-12-02")).is_err());
+        assert!(seed_for_draw(&mut storage, &key, POOL, 2025, day("2025-12-05")).is_err());
+
+        // Claire's reveal closed commitments
+        let (_, commitment) = new_value(POOL, 2025, "Anne").unwrap();
+        assert!(record_commitment(&mut storage, POOL, 2025, "Anne", &commitment, day("2025-12-02")).is_err());
+    }
+
+    #[test]
+    fn unrevealed_contributions_are_forfeited() {
+        let (mut storage, _path, key, [(claire, claire_value), (grant, grant_value)]) = open_with_commitments("forfeit");
+        record_reveal(&mut storage, POOL, 2025, &claire, &claire_value, day("2025-12-05")).unwrap();
+        let (seed, contributions) = seed_for_draw(&mut storage, &key, POOL, 2025, day("2025-12-06")).unwrap().unwrap();
+
+        let status = |name: &str| contributions.iter().find(|c| c.participant == name).unwrap().status;
+        assert_eq!(status(ORGANIZER), ContributionStatus::Used);
+        assert_eq!(status(&claire), ContributionStatus::Used);
+        assert_eq!(status(&grant), ContributionStatus::Forfeited);
+        verify(&contributions, seed).unwrap();
+
+        // Revealing after the draw doesn't count, and redrawing reuses the same seed
+        assert!(record_reveal(&mut storage, POOL, 2025, &grant, &grant_value, day("2025-12-05")).is_err());
+        let (redrawn, _) = seed_for_draw(&mut storage, &key, POOL, 2025, day("2025-12-07")).unwrap().unwrap();
+        assert_eq!(seed, redrawn);
+    }
+
+    #[test]
+    fn late_reveals_and_commitments_are_refused() {
+        let (mut storage, _path, _, [(claire, value), _]) = open_with_commitments("late");
+        assert!(record_reveal(&mut storage, POOL, 2025, &claire, &value, day("2025-12-06")).is_err());
+        let (_, commitment) = new_value(POOL, 2025, "Anne").unwrap();
+        assert!(record_commitment(&mut storage, POOL, 2025, "Anne", &commitment, day("2025-12-06")).is_err());
+    }
+
+    #[test]
+    fn round_must_be_opened_once_with_a_future_deadline() {
+        let path = TempPath::new("seed-open");
+        let mut storage = path.storage();
+        let key = VaultKey::generate().unwrap();
+        let (_, commitment) = new_value(POOL, 2025, "Claire").unwrap();
+        assert!(record_commitment(&mut storage, POOL, 2025, "Claire", &commitment, day("2025-12-01")).is_err());
+        assert!(open_round(&mut storage, &key, POOL, 2025, day("2025-11-30"), day("2025-12-01")).is_err());
+
+        open_round(&mut storage, &key, POOL, 2025, day("2025-12-05"), day("2025-12-01")).unwrap();
+        let contributions = storage.load_seed_contributions(POOL, 2025).unwrap();
+        assert_eq!(reveal_deadline(&contributions).unwrap(), Some(day("2025-12-05")));
+        assert!(open_round(&mut storage, &key, POOL, 2025, day("2025-12-10"), day("2025-12-01")).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data,
+    giftexchange::ExchangePool,
+    notify::{Event, Notifier},
+    storage::Storage,
+};
+
+/// Environment variable with how many days before each date reminders go out,
+/// comma-separated, e.g. `7,1`. Empty or `none` turns reminders off.
+pub const REMINDER_DAYS_ENV: &str = "CHRISTMAS_REMINDER_DAYS";
+
+const DEFAULT_REMINDER_DAYS: &[i64] = &[7, 1];
+
+/// Which of a pool's dates a reminder is about
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderKind {
+    /// The last day to buy gifts, from `data::deadline_for_pool`
+    Deadline,
+    /// The day gifts are exchanged, from `data::exchange_date_for_pool`
+    Exchange,
+}
+
+impl ReminderKind {
+    pub fn slug(&self) -> &'static str {
+        match self {
+            ReminderKind::Deadline => "deadline",
+            ReminderKind::Exchange => "exchange",
+        }
+    }
+}
+
+/// A reminder that has gone out, kept so it's never sent twice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentReminder {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub kind: ReminderKind,
+    /// The offset the reminder was sent for, from `CHRISTMAS_REMINDER_DAYS`
+    pub days_before: i64,
+    pub sent_at: String,
+}
+
+impl SentReminder {
+    /// Whether both are for the same date of the same exchange, at the same offset
+    pub fn is_for(&self, other: &SentReminder) -> bool {
+        self.pool == other.pool
+            && self.year == other.year
+            && self.kind == other.kind
+            && self.days_before == other.days_before
+    }
+}
+
+/// A reminder that's due, and the event that sends it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueReminder {
+    pub kind: ReminderKind,
+    pub days_before: i64,
+    pub event: Event,
+}
+
+/// The reminder offsets from `CHRISTMAS_REMINDER_DAYS`, or the default of a week and a day before
+pub fn reminder_days() -> Result<Vec<i64>> {
+    let Ok(value) = std::env::var(REMINDER_DAYS_ENV) else {
+        return Ok(DEFAULT_REMINDER_DAYS.to_vec());
+    };
+    if value.trim().is_empty() || value.trim() == "none" {
+        return Ok(vec![]);
+    }
+    value
+        .split(',')
+        .map(|days| match days.trim().parse::<i64>() {
+            Ok(days) if days >= 0 => Ok(days),
+            _ => anyhow::bail!("{REMINDER_DAYS_ENV} must be whole numbers of days, e.g. 7,1, not '{value}'"),
+        })
+        .collect()
+}
+
+/// Says which pools have no buy-by date in `season`, so get no buy-by reminders, if any
+pub fn missing_deadlines_note(season: i32) -> Option<String> {
+    let pools: Vec<String> = ExchangePool::ALL
+        .into_iter()
+        .filter(|pool| data::deadline_for_pool(*pool, season).is_none())
+        .map(|pool| pool.to_string())
+        .collect();
+    if pools.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Buy-by reminders are off for {} until data::deadline_for_pool sets a date.",
+        pools.join(", ")
+    ))
+}
+
+/// The reminders due `today` for draws saved in `season`
+///
+/// For each pool with a saved draw, a date's reminder is due once `today` is
+/// within its offset. Only the nearest offset reached is due, so a scheduler
+/// that was down for a week sends one reminder rather than catching up on
+/// every one it missed, and nothing is due again once it's been recorded.
+pub fn due(storage: &dyn Storage, season: i32, today: NaiveDate, offsets: &[i64]) -> Result<Vec<DueReminder>> {
+    let pairings = storage.load_pairings()?;
+    let mut due = vec![];
+    for pool in ExchangePool::ALL {
+        if !pairings.iter().any(|p| p.pool == pool && p.year == season) {
+            continue;
+        }
+        let sent = storage.load_sent_reminders(pool, season)?;
+        let dates = [
+            (ReminderKind::Deadline, data::deadline_for_pool(pool, season)),
+            (ReminderKind::Exchange, data::exchange_date_for_pool(pool, season)),
+        ];
+        for (kind, date) in dates {
+            let Some(date) = date else { continue };
+            let days_left = (date - today).num_days();
+            if days_left < 0 {
+                continue;
+            }
+            let Some(days_before) = offsets.iter().copied().filter(|days| *days >= days_left).min() else {
+                continue;
+            };
+            if sent.iter().any(|s| s.kind == kind && s.days_before <= days_before) {
+                continue;
+            }
+            let event = match kind {
+                ReminderKind::Deadline => Event::DeadlineApproaching {
+                    pool,
+                    year: season,
+                    deadline: date,
+                    days_left,
+                },
+                ReminderKind::Exchange => Event::ExchangeApproaching {
+                    pool,
+                    year: season,
+                    date,
+                    days_left,
+                },
+            };
+            due.push(DueReminder { kind, days_before, event });
+        }
+    }
+    Ok(due)
+}
+
+/// Sends the reminders due `today` through `notifiers`
+///
+/// Each reminder is recorded before it's sent, and skipped if it already
+/// was, so a CLI run overlapping the server's scheduler can't send it twice.
+/// A reminder that fails is forgotten again, so it's tried again next time;
+/// one whose run stops between recording and sending it isn't sent at all.
+/// Returns the reminders sent.
+pub fn send_due(
+    storage: &mut dyn Storage,
+    notifiers: &[Box<dyn Notifier>],
+    season: i32,
+    today: NaiveDate,
+    offsets: &[i64],
+) -> Result<Vec<DueReminder>> {
+    let mut sent = vec![];
+    for reminder in due(storage, season, today, offsets)? {
+        let record = SentReminder {
+            pool: reminder.event.pool(),
+            year: season,
+            kind: reminder.kind,
+            days_before: reminder.days_before,
+            sent_at: chrono::Utc::now().to_rfc3339(),
+        };
+        if !storage.record_reminder(&record)? {
+            continue;
+        }
+        let delivered = notifiers.iter().try_for_each(|notifier| notifier.notify(&reminder.event));
+        if let Err(e) = delivered {
+            eprintln!(
+                "Warning: Failed to send the {} {season} {} reminder, will retry: {e:#}",
+                reminder.event.pool(),
+                reminder.kind.slug()
+            );
+            storage.forget_reminder(&record)?;
+            continue;
+        }
+        sent.push(reminder);
+    }
+    Ok(sent)
+}
+
+#[cfg(feature = "server")]
+pub use scheduler::start_scheduler;
+
+#[cfg(feature = "server")]
+mod scheduler {
+    use anyhow::Result;
+    use std::time::Duration;
+
+    use crate::{notify, season::SeasonCutover, storage};
+
+    /// How often the scheduler checks for due reminders
+    const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    /// Starts a background thread that sends due reminders every hour, while
+    /// the server runs
+    ///
+    /// Does nothing if reminders are turned off or no notifier is configured,
+    /// and notes which pools have no buy-by date to remind about.
+    pub fn start_scheduler() {
+        let offsets = match super::reminder_days() {
+            Ok(offsets) if offsets.is_empty() => return,
+            Ok(offsets) => offsets,
+            Err(e) => {
+                eprintln!("Warning: Reminders are off: {e:#}");
+                return;
+            }
+        };
+        match notify::notifiers() {
+            Ok(notifiers) if notifiers.is_empty() => return,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Warning: Reminders are off: {e:#}");
+                return;
+            }
+        }
+
+        if let Some(note) = SeasonCutover::from_env()
+            .ok()
+            .and_then(|cutover| super::missing_deadlines_note(cutover.season_for(chrono::Local::now().date_naive())))
+        {
+            eprintln!("Note: {note}");
+        }
+
+        std::thread::spawn(move || loop {
+            if let Err(e) = check(&offsets) {
+                eprintln!("Warning: Failed to check for due reminders: {e:#}");
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        });
+    }
+
+    fn check(offsets: &[i64]) -> Result<()> {
+        let today = chrono::Local::now().date_naive();
+        let season = SeasonCutover::from_env()?.season_for(today);
+        let mut storage = storage::open_default()?;
+        for reminder in super::send_due(storage.as_mut(), &notify::notifiers()?, season, today, offsets)? {
+            println!("Sent reminder: {}", reminder.event.message());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TempPath, vault::EncryptedPairing};
+
+    /// Storage with an Island Life draw for 2025, whose exchange is on Christmas Day
+    fn drawn(path: &TempPath) -> crate::storage::JsonStorage {
+        let mut storage = path.storage();
+        let pairing = |giver: &str, receiver: &str| EncryptedPairing {
+            year: 2025,
+            pool: ExchangePool::IslandLife,
+            giver: giver.to_string(),
+            receiver: receiver.to_string(),
+        };
+        storage
+            .save_draw(ExchangePool::IslandLife, 2025, &[], &[pairing("Alec", "Will"), pairing("Will", "Alec")])
+            .unwrap();
+        storage
+    }
+
+    fn days_left(days: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 12, 25).unwrap() - chrono::Days::new(days as u64)
+    }
+
+    /// `(days_before, days_left)` of each reminder due with `days` left
+    fn due_with(storage: &dyn Storage, days: i64, offsets: &[i64]) -> Vec<(i64, i64)> {
+        due(storage, 2025, days_left(days), offsets)
+            .unwrap()
+            .into_iter()
+            .map(|reminder| {
+                assert_eq!(reminder.kind, ReminderKind::Exchange);
+                assert_eq!(reminder.event.pool(), ExchangePool::IslandLife);
+                (reminder.days_before, reminder.event.date().unwrap().1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nearest_offset_reached_is_due() {
+        let path = TempPath::new("reminders-due");
+        let storage = drawn(&path);
+        assert_eq!(due_with(&storage, 8, &[7, 1]), []);
+        assert_eq!(due_with(&storage, 7, &[7, 1]), [(7, 7)]);
+        assert_eq!(due_with(&storage, 3, &[7, 1]), [(7, 3)]);
+        assert_eq!(due_with(&storage, 1, &[7, 1]), [(1, 1)]);
+        assert_eq!(due_with(&storage, 0, &[7, 1]), [(1, 0)]);
+        assert_eq!(due_with(&storage, 0, &[7, 1, 0]), [(0, 0)]);
+        // Past the date, or without the draw for the season, nothing is due
+        assert_eq!(due(&storage, 2025, days_left(0).succ_opt().unwrap(), &[7, 1]).unwrap(), []);
+        assert_eq!(due(&storage, 2026, NaiveDate::from_ymd_opt(2026, 12, 24).unwrap(), &[7, 1]).unwrap(), []);
+    }
+
+    #[test]
+    fn sent_reminders_arent_due_again() {
+        let path = TempPath::new("reminders-sent");
+        let mut storage = drawn(&path);
+        let sent = SentReminder {
+            pool: ExchangePool::IslandLife,
+            year: 2025,
+            kind: ReminderKind::Exchange,
+            days_before: 7,
+            sent_at: "2025-12-18T09:00:00+00:00".to_string(),
+        };
+        assert!(storage.record_reminder(&sent).unwrap());
+        assert_eq!(due_with(&storage, 7, &[7, 1]), []);
+        assert_eq!(due_with(&storage, 3, &[7, 1]), []);
+        assert_eq!(due_with(&storage, 1, &[7, 1]), [(1, 1)]);
+    }
+
+    #[test]
+    fn no_offsets_means_nothing_is_due() {
+        let path = TempPath::new("reminders-none");
+        let storage = drawn(&path);
+        for days in [7, 1, 0] {
+            assert_eq!(due_with(&storage, days, &[]), []);
+        }
+    }
+}
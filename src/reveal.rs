@@ -0,0 +1,314 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audit::{self, to_hex, AuditAction, AuditEntry},
+    blind,
+    giftexchange::ExchangePool,
+    history,
+    storage::Storage,
+    vault::{self, VaultKey},
+};
+
+/// Environment variable with the web UI's address, used to build reveal links
+pub const BASE_URL_ENV: &str = "CHRISTMAS_BASE_URL";
+
+/// Where the web UI is served when `CHRISTMAS_BASE_URL` isn't set
+pub const DEFAULT_BASE_URL: &str = "http://localhost:8080";
+
+/// A reveal link issued to one giver
+///
+/// Only a hash of the token is stored, so a copy of the storage can't be
+/// used to open anyone's link. The giver's receiver is kept encrypted under a
+/// key derived from the token, so the link can read it without the admin key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevealToken {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub giver: String,
+    pub token_hash: String,
+    /// The receiver, encrypted under `VaultKey::for_token`
+    #[serde(default)]
+    pub encrypted_receiver: String,
+    /// The token itself, encrypted under the admin key so a regenerated draw
+    /// can be re-encrypted for it
+    #[serde(default)]
+    pub encrypted_token: String,
+}
+
+/// What a giver is shown when they open their reveal link
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revealed {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub giver: String,
+    pub receiver: String,
+}
+
+/// Hex SHA-256 of a reveal token, as it's stored
+pub fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// A new unguessable token from the operating system's random number generator
+fn new_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).context("Failed to generate a reveal token")?;
+    Ok(to_hex(&bytes))
+}
+
+/// Issues a new reveal token for every giver in the saved draw for `pool` in `year`
+///
+/// Returns each giver with their token. Tokens issued earlier for the same
+/// exchange stop working. A token keeps working if the draw is regenerated,
+/// and then shows the giver's new receiver.
+pub fn issue_tokens(
+    storage: &mut dyn Storage,
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+) -> Result<Vec<(String, String)>> {
+    let mut pairings = history::pool_pairings(&vault::decrypt_pairings(key, &storage.load_pairings()?)?, pool, year);
+    if pairings.is_empty() {
+        anyhow::bail!("There is no saved {pool} draw for {year} to issue reveal links for.");
+    }
+    pairings.sort();
+
+    let mut issued = vec![];
+    let mut tokens = vec![];
+    for (giver, receiver) in pairings {
+        let token = new_token()?;
+        tokens.push(seal_token(key, pool, year, &giver, &receiver, &token)?);
+        issued.push((giver, token));
+    }
+    storage.save_reveal_tokens(pool, year, &tokens)?;
+    Ok(issued)
+}
+
+/// Issues new reveal tokens for `pool` in `year` to show to `actor`, logging that they were
+///
+/// Blind organizer mode refuses, since each link gives its giver's pairing away,
+/// unless `break_glass` gives a reason, which is logged as a break-glass reveal.
+/// Emailing the links hands them out without showing them.
+pub fn hand_out_tokens(
+    storage: &mut dyn Storage,
+    pool: ExchangePool,
+    year: i32,
+    actor: &str,
+    break_glass: Option<&str>,
+) -> Result<Vec<(String, String)>> {
+    let sealed = blind::is_sealed(year)?;
+    if sealed && break_glass.is_none_or(|reason| reason.trim().is_empty()) {
+        anyhow::bail!(
+            "Blind organizer mode is on, so the {pool} reveal links for {year} can't be shown: each one gives \
+             its giver's pairing away. Send them with `cargo run -- email {slug}`, or use \
+             `cargo run -- links {slug} --break-glass <reason>` if they must be seen.",
+            slug = pool.slug()
+        );
+    }
+
+    let issued = issue_tokens(storage, &VaultKey::admin()?, pool, year)?;
+    match break_glass.filter(|_| sealed) {
+        Some(reason) => {
+            let mut entry = AuditEntry::new(actor, AuditAction::BreakGlass, pool, year);
+            entry.detail = Some(format!("reveal links: {}", reason.trim()));
+            storage.append_audit(&entry)?;
+        }
+        None => audit::log_reveal(storage, actor, pool, year, "reveal links issued")?,
+    }
+    Ok(issued)
+}
+
+/// Each giver's current reveal token for `pool` in `year`, issuing tokens if
+/// none have been yet
+///
+/// Tokens already issued are reused, so links sent out earlier keep working.
+pub fn current_tokens(storage: &mut dyn Storage, key: &VaultKey, pool: ExchangePool, year: i32) -> Result<Vec<(String, String)>> {
+    let tokens = storage.load_reveal_tokens(pool, year)?;
+    if tokens.is_empty() {
+        return issue_tokens(storage, key, pool, year);
+    }
+
+    vault::givers(&storage.load_pairings()?, pool, year)
+        .into_iter()
+        .map(|giver| {
+            let token = tokens
+                .iter()
+                .find(|token| token.giver == giver && !token.encrypted_token.is_empty())
+                .with_context(|| {
+                    format!("{giver} has no current reveal link. Issue new ones with `cargo run -- links {}`.", pool.slug())
+                })?;
+            let plain = key.decrypt(&token.encrypted_token, &token_context(&token.token_hash))?;
+            Ok((giver, plain))
+        })
+        .collect()
+}
+
+/// Re-encrypts the reveal tokens for `pool` in `year` for a regenerated
+/// `exchange`, so links already sent out show the new receivers
+///
+/// Tokens for givers no longer in the draw are dropped.
+pub fn reencrypt_tokens(
+    storage: &mut dyn Storage,
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+    exchange: &[(String, String)],
+) -> Result<()> {
+    let tokens = storage.load_reveal_tokens(pool, year)?;
+    if tokens.is_empty() {
+        return Ok(());
+    }
+
+    let mut updated = vec![];
+    for token in tokens {
+        let Some((_, receiver)) = exchange.iter().find(|(giver, _)| *giver == token.giver) else {
+            continue;
+        };
+        if token.encrypted_token.is_empty() {
+            // Issued before pairings were encrypted, so it can't show anything anyway
+            continue;
+        }
+        let plain = key.decrypt(&token.encrypted_token, &token_context(&token.token_hash))?;
+        updated.push(seal_token(key, pool, year, &token.giver, receiver, &plain)?);
+    }
+    storage.save_reveal_tokens(pool, year, &updated)
+}
+
+/// The stored form of `token`, with `receiver` encrypted for it
+fn seal_token(
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+    giver: &str,
+    receiver: &str,
+    token: &str,
+) -> Result<RevealToken> {
+    let token_hash = hash_token(token);
+    Ok(RevealToken {
+        pool,
+        year,
+        giver: giver.to_string(),
+        encrypted_receiver: VaultKey::for_token(token).encrypt(receiver, &vault::pairing_context(pool, year, giver))?,
+        encrypted_token: key.encrypt(token, &token_context(&token_hash))?,
+        token_hash,
+    })
+}
+
+/// What a token is encrypted against under the admin key
+fn token_context(token_hash: &str) -> String {
+    format!("reveal-token/{token_hash}")
+}
+
+/// Looks up whose link `token` is and who they give to, logging the reveal
+/// under `actor`, or under the link's giver when it's opened from their reveal page
+///
+/// The receiver is decrypted with the token alone, so this doesn't need the admin key.
+pub fn reveal(storage: &mut dyn Storage, token: &str, actor: Option<&str>) -> Result<Revealed> {
+    let revealed = open(storage, token)?;
+    let detail = format!("{}'s reveal link", revealed.giver);
+    audit::log_reveal(storage, actor.unwrap_or(&revealed.giver), revealed.pool, revealed.year, &detail)?;
+    Ok(revealed)
+}
+
+/// Like `reveal`, but without logging, for pages that act on a link already opened
+pub fn open(storage: &dyn Storage, token: &str) -> Result<Revealed> {
+    let found = storage
+        .find_reveal_token(&hash_token(token))?
+        .context("This reveal link isn't valid. Ask the organizer for a new one.")?;
+    if found.encrypted_receiver.is_empty() {
+        anyhow::bail!("This reveal link was issued before pairings were encrypted. Ask the organizer for a new one.");
+    }
+    let receiver = VaultKey::for_token(token)
+        .decrypt(
+            &found.encrypted_receiver,
+            &vault::pairing_context(found.pool, found.year, &found.giver),
+        )
+        .context("This reveal link couldn't be read. Ask the organizer for a new one.")?;
+
+    Ok(Revealed {
+        pool: found.pool,
+        year: found.year,
+        giver: found.giver,
+        receiver,
+    })
+}
+
+/// The web UI address of the reveal page for `token`
+pub fn reveal_url(token: &str) -> String {
+    let base = std::env::var(BASE_URL_ENV).unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+    format!("{}/reveal/{token}", base.trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempPath;
+
+    fn save_draw(storage: &mut dyn Storage, key: &VaultKey, exchange: &[(&str, &str)]) -> Vec<(String, String)> {
+        let exchange: Vec<(String, String)> =
+            exchange.iter().map(|(giver, receiver)| (giver.to_string(), receiver.to_string())).collect();
+        let pairings = vault::encrypt_exchange(key, ExchangePool::IslandLife, 2024, &exchange).unwrap();
+        storage.save_draw(ExchangePool::IslandLife, 2024, &[], &pairings).unwrap();
+        exchange
+    }
+
+    fn receiver(storage: &dyn Storage, token: &str) -> Result<String> {
+        open(storage, token).map(|revealed| revealed.receiver)
+    }
+
+    #[test]
+    fn tokens_open_only_their_own_receiver() {
+        let path = TempPath::new("reveal-own");
+        let mut storage = path.storage();
+        let key = VaultKey::generate().unwrap();
+        save_draw(&mut storage, &key, &[("Alec", "Will"), ("Will", "Grace"), ("Grace", "Alec")]);
+
+        let issued = issue_tokens(&mut storage, &key, ExchangePool::IslandLife, 2024).unwrap();
+        let givers: Vec<&str> = issued.iter().map(|(giver, _)| giver.as_str()).collect();
+        assert_eq!(givers, ["Alec", "Grace", "Will"]);
+        let stored = storage.load_reveal_tokens(ExchangePool::IslandLife, 2024).unwrap();
+        for (giver, token) in &issued {
+            let revealed = open(&storage, token).unwrap();
+            assert_eq!(revealed.giver, *giver);
+            // The token's key can't read anyone else's receiver
+            for other in stored.iter().filter(|t| t.giver != *giver) {
+                let context = vault::pairing_context(other.pool, other.year, &other.giver);
+                assert!(VaultKey::for_token(token).decrypt(&other.encrypted_receiver, &context).is_err());
+            }
+        }
+        assert_eq!(receiver(&storage, &issued[0].1).unwrap(), "Will");
+        assert!(receiver(&storage, "not-a-token").is_err());
+        // Storage keeps neither the tokens nor the receivers in the clear
+        assert!(stored.iter().all(|t| !t.encrypted_receiver.contains("Will") && t.token_hash != issued[0].1));
+    }
+
+    #[test]
+    fn redraws_keep_tokens_until_they_are_reissued() {
+        let path = TempPath::new("reveal-redraw");
+        let mut storage = path.storage();
+        let key = VaultKey::generate().unwrap();
+        save_draw(&mut storage, &key, &[("Alec", "Will"), ("Will", "Grace"), ("Grace", "Alec")]);
+        let issued = issue_tokens(&mut storage, &key, ExchangePool::IslandLife, 2024).unwrap();
+        let token = |giver: &str| issued.iter().find(|(g, _)| g == giver).unwrap().1.clone();
+
+        // A regenerated draw shows the new receivers through the links already sent
+        let exchange = save_draw(&mut storage, &key, &[("Alec", "Grace"), ("Grace", "Will"), ("Will", "Alec")]);
+        reencrypt_tokens(&mut storage, &key, ExchangePool::IslandLife, 2024, &exchange).unwrap();
+        assert_eq!(receiver(&storage, &token("Alec")).unwrap(), "Grace");
+        assert_eq!(receiver(&storage, &token("Will")).unwrap(), "Alec");
+
+        // Givers left out of the new draw lose their link
+        let exchange = save_draw(&mut storage, &key, &[("Alec", "Will"), ("Will", "Alec")]);
+        reencrypt_tokens(&mut storage, &key, ExchangePool::IslandLife, 2024, &exchange).unwrap();
+        assert_eq!(receiver(&storage, &token("Alec")).unwrap(), "Will");
+        assert!(receiver(&storage, &token("Grace")).is_err());
+
+        // Issuing new links replaces the old ones
+        let reissued = issue_tokens(&mut storage, &key, ExchangePool::IslandLife, 2024).unwrap();
+        assert!(receiver(&storage, &token("Alec")).is_err());
+        assert_eq!(receiver(&storage, &reissued[0].1).unwrap(), "Will");
+        assert_eq!(current_tokens(&mut storage, &key, ExchangePool::IslandLife, 2024).unwrap(), reissued);
+    }
+}
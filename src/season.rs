@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, NaiveDate};
+
+/// Environment variable that sets the day a new exchange season starts, as `MM-DD`
+pub const SEASON_CUTOVER_ENV: &str = "CHRISTMAS_SEASON_CUTOVER";
+
+/// The day a new exchange season starts each year
+///
+/// Dates before the cutover belong to the previous year's season, so with the
+/// default of February 1st a draw run on January 2nd is filed under last
+/// Christmas rather than the coming one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SeasonCutover {
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Default for SeasonCutover {
+    fn default() -> Self {
+        SeasonCutover { month: 2, day: 1 }
+    }
+}
+
+impl SeasonCutover {
+    /// Parses a cutover written as `MM-DD`, e.g. `02-01`
+    pub fn parse(value: &str) -> Result<Self> {
+        let (month, day) = value
+            .split_once('-')
+            .with_context(|| format!("Invalid season cutover '{value}', expected MM-DD"))?;
+        let month: u32 = month
+            .parse()
+            .with_context(|| format!("Invalid month in season cutover '{value}'"))?;
+        let day: u32 = day
+            .parse()
+            .with_context(|| format!("Invalid day in season cutover '{value}'"))?;
+        // 2024 is a leap year, so February 29th is accepted
+        if NaiveDate::from_ymd_opt(2024, month, day).is_none() {
+            anyhow::bail!("Invalid season cutover '{value}'");
+        }
+        Ok(SeasonCutover { month, day })
+    }
+
+    /// The cutover from `CHRISTMAS_SEASON_CUTOVER`, or the default if it's unset or empty
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(SEASON_CUTOVER_ENV) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(value.trim()),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// The season `date` belongs to
+    pub fn season_for(&self, date: NaiveDate) -> i32 {
+        if (date.month(), date.day()) < (self.month, self.day) {
+            date.year() - 1
+        } else {
+            date.year()
+        }
+    }
+}
+
+/// The season today belongs to, using the configured cutover
+pub fn current_season() -> Result<i32> {
+    Ok(SeasonCutover::from_env()?.season_for(Local::now().date_naive()))
+}
+
+/// Parses an explicit season such as `2024`
+pub fn parse_season(value: &str) -> Result<i32> {
+    value.parse().with_context(|| format!("Invalid season: '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn default_cutover_is_february_first() {
+        let cutover = SeasonCutover::default();
+        assert_eq!(cutover.season_for(day("2025-01-31")), 2024);
+        assert_eq!(cutover.season_for(day("2025-02-01")), 2025);
+        assert_eq!(cutover.season_for(day("2025-12-31")), 2025);
+        assert_eq!(SeasonCutover::parse("02-01").unwrap(), cutover);
+    }
+
+    #[test]
+    fn custom_cutover_moves_the_boundary() {
+        let cutover = SeasonCutover::parse("12-26").unwrap();
+        assert_eq!(cutover.season_for(day("2025-12-25")), 2024);
+        assert_eq!(cutover.season_for(day("2025-12-26")), 2025);
+        assert_eq!(cutover.season_for(day("2026-02-01")), 2025);
+    }
+
+    #[test]
+    fn invalid_cutovers_are_refused() {
+        for value in ["13-01", "02-30", "00-10", "0201", "feb-01", ""] {
+            assert!(SeasonCutover::parse(value).is_err(), "{value} was accepted");
+        }
+        assert_eq!(SeasonCutover::parse("02-29").unwrap(), SeasonCutover { month: 2, day: 29 });
+    }
+
+    #[test]
+    fn empty_env_value_means_the_default() {
+        // The only test that sets this variable, so it can't race another
+        std::env::set_var(SEASON_CUTOVER_ENV, " ");
+        assert_eq!(SeasonCutover::from_env().unwrap(), SeasonCutover::default());
+        std::env::set_var(SEASON_CUTOVER_ENV, "12-26");
+        assert_eq!(SeasonCutover::from_env().unwrap(), SeasonCutover { month: 12, day: 26 });
+        std::env::set_var(SEASON_CUTOVER_ENV, "13-01");
+        assert!(SeasonCutover::from_env().is_err());
+        std::env::remove_var(SEASON_CUTOVER_ENV);
+    }
+}
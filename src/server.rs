@@ -0,0 +1,329 @@
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    giftexchange::ExchangePool,
+    lifecycle::ExchangeStatus,
+    messages::MessageBoard,
+    stats::PairStats,
+    themes::GiftTheme,
+    wishlist::{NewWish, WishlistBoard},
+};
+
+#[cfg(feature = "server")]
+use anyhow::Context;
+#[cfg(feature = "server")]
+use crate::{
+    audit, blind, data, draw, history, lifecycle, messages, notify, organizer, reveal, season, slips,
+    storage::{self, Storage},
+    utils::theme_for_pool,
+    vault, wishlist,
+};
+
+/// Cookie that keeps an organizer signed in to the web UI
+#[cfg(feature = "server")]
+const SESSION_COOKIE: &str = "christmas_session";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExchangePairing {
+    pub giver: String,
+    pub receiver: String,
+}
+
+/// The organizer's view of a draw, without the pairings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeResult {
+    /// Everyone giving in the draw, sorted by name
+    pub givers: Vec<String>,
+    pub theme: GiftTheme,
+    pub year: i32,
+    pub status: ExchangeStatus,
+    /// Blind organizer mode is on, so the pairings can't be shown
+    pub blind: bool,
+}
+
+/// A reveal link token issued to one giver
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RevealLink {
+    pub giver: String,
+    pub token: String,
+}
+
+/// Everything a giver sees on their reveal page
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reveal {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub giver: String,
+    pub receiver: String,
+    pub theme: GiftTheme,
+    pub budget: Option<String>,
+}
+
+/// Signs in the organizer whose sign-in key is `key`, returning their name
+///
+/// The session is kept in an HTTP-only cookie, which every organizer function checks.
+#[server]
+pub async fn sign_in(key: String) -> Result<String, ServerFnError> {
+    let sign_in = || -> anyhow::Result<String> {
+        let name = organizer::authenticate(&key)?;
+        let token = organizer::start_session(&name)?;
+        set_session_cookie(&token, organizer::SESSION_LENGTH.as_secs())?;
+        Ok(name)
+    };
+    sign_in().map_err(server_error)
+}
+
+/// Signs out the organizer signed in on this browser
+#[server]
+pub async fn sign_out() -> Result<(), ServerFnError> {
+    if let Some(token) = session_token() {
+        organizer::end_session(&token);
+    }
+    set_session_cookie("", 0).map_err(server_error)
+}
+
+/// The organizer signed in on this browser, if anyone is
+#[server]
+pub async fn current_organizer() -> Result<Option<String>, ServerFnError> {
+    Ok(session_token().and_then(|token| organizer::session_organizer(&token)))
+}
+
+/// Returns the current season's saved draw for `pool`, or `None` if it hasn't been drawn yet
+#[server]
+pub async fn load_exchange(pool: ExchangePool) -> Result<Option<ExchangeResult>, ServerFnError> {
+    as_organizer(|_, season| load_saved(pool, season))
+}
+
+/// Draws an exchange for `pool` and saves it, replacing the current season's draw if there is one
+///
+/// Fails if the season's draw has been finalized.
+#[server]
+pub async fn regenerate_exchange(pool: ExchangePool) -> Result<ExchangeResult, ServerFnError> {
+    as_organizer(|organizer, season| generate_exchange(pool, season, organizer))
+}
+
+/// Locks the current season's draw for `pool` so it can't be regenerated
+#[server]
+pub async fn finalize_exchange(pool: ExchangePool) -> Result<ExchangeResult, ServerFnError> {
+    as_organizer(|organizer, season| {
+        let event = lifecycle::finalize(storage::open_default()?.as_mut(), pool, season, organizer)?;
+        // Webhook retries can take most of a minute, which would hold up this worker
+        std::thread::spawn(move || notify::send_or_warn(&event));
+        load_saved(pool, season)?.with_context(|| format!("There is no saved {pool} draw for {season}."))
+    })
+}
+
+/// Every pairing in the current season's draw for `pool`, logged as a reveal
+///
+/// Fails in blind organizer mode.
+#[server]
+pub async fn show_pairings(pool: ExchangePool) -> Result<Vec<ExchangePairing>, ServerFnError> {
+    as_organizer(|organizer, season| {
+        blind::ensure_unsealed(pool, season)?;
+        let mut storage = storage::open_default()?;
+        let pairings = history::pool_pairings(&vault::load_pairings(storage.as_ref())?, pool, season);
+        audit::log_reveal(storage.as_mut(), organizer, pool, season, "pairings shown in the web UI")?;
+        Ok(pairings
+            .into_iter()
+            .map(|(giver, receiver)| ExchangePairing { giver, receiver })
+            .collect())
+    })
+}
+
+/// Printable slips for every giver in the current season's draw for `pool`, as
+/// HTML, logged as a reveal
+///
+/// Fails in blind organizer mode.
+#[server]
+pub async fn printable_slips(pool: ExchangePool) -> Result<String, ServerFnError> {
+    as_organizer(|organizer, season| {
+        blind::ensure_unsealed(pool, season)?;
+        let mut storage = storage::open_default()?;
+        let slips = slips::slips(storage.as_mut(), &vault::VaultKey::admin()?, pool, season)?;
+        audit::log_reveal(storage.as_mut(), organizer, pool, season, "printable slips shown in the web UI")?;
+        Ok(slips::render(pool, season, &slips))
+    })
+}
+
+/// Issues a new reveal link for every giver in the current season's draw for `pool`, logged
+///
+/// Fails in blind organizer mode, where links are only handed out by email.
+#[server]
+pub async fn issue_reveal_links(pool: ExchangePool) -> Result<Vec<RevealLink>, ServerFnError> {
+    as_organizer(|organizer, season| {
+        let issued = reveal::hand_out_tokens(storage::open_default()?.as_mut(), pool, season, organizer, None)?;
+        Ok(issued
+            .into_iter()
+            .map(|(giver, token)| RevealLink { giver, token })
+            .collect())
+    })
+}
+
+/// Shows the giver whose link `token` is who they give to
+#[server]
+pub async fn reveal_recipient(token: String) -> Result<Reveal, ServerFnError> {
+    let reveal = || -> anyhow::Result<Reveal> {
+        let mut storage = storage::open_default()?;
+        let revealed = reveal::reveal(storage.as_mut(), &token, None)?;
+        Ok(Reveal {
+            theme: theme_for_pool(storage.as_mut(), revealed.pool, revealed.year),
+            budget: data::budget_for_pool(revealed.pool).map(str::to_string),
+            pool: revealed.pool,
+            year: revealed.year,
+            giver: revealed.giver,
+            receiver: revealed.receiver,
+        })
+    };
+    reveal().map_err(server_error)
+}
+
+/// The message threads the giver whose link `token` is can read
+#[server]
+pub async fn load_messages(token: String) -> Result<MessageBoard, ServerFnError> {
+    storage::open_default()
+        .and_then(|storage| messages::board(storage.as_ref(), &token))
+        .map_err(server_error)
+}
+
+/// Sends a message from the giver whose link `token` is to the person they give to, without naming them
+#[server]
+pub async fn message_recipient(token: String, body: String) -> Result<MessageBoard, ServerFnError> {
+    storage::open_default()
+        .and_then(|mut storage| messages::message_recipient(storage.as_mut(), &token, &body))
+        .map_err(server_error)
+}
+
+/// Sends a message from the giver whose link `token` is to whoever gives to them
+#[server]
+pub async fn message_santa(token: String, body: String) -> Result<MessageBoard, ServerFnError> {
+    storage::open_default()
+        .and_then(|mut storage| messages::message_santa(storage.as_mut(), &token, &body))
+        .map_err(server_error)
+}
+
+/// The recipient's wishlist and their own for the giver whose link `token` is
+#[server]
+pub async fn load_wishlists(token: String) -> Result<WishlistBoard, ServerFnError> {
+    storage::open_default()
+        .and_then(|mut storage| wishlist::board(storage.as_mut(), &token))
+        .map_err(server_error)
+}
+
+/// Adds an idea to the wishlist of the giver whose link `token` is
+#[server]
+pub async fn add_wish(token: String, wish: NewWish) -> Result<WishlistBoard, ServerFnError> {
+    storage::open_default()
+        .and_then(|mut storage| wishlist::add_wish(storage.as_mut(), &token, &wish))
+        .map_err(server_error)
+}
+
+/// Removes the idea at `index` from the wishlist of the giver whose link `token` is
+#[server]
+pub async fn remove_wish(token: String, index: usize) -> Result<WishlistBoard, ServerFnError> {
+    storage::open_default()
+        .and_then(|mut storage| wishlist::remove_wish(storage.as_mut(), &token, index))
+        .map_err(server_error)
+}
+
+/// Pair statistics for `pool` from draws before the current season, so the
+/// heatmap doesn't give away this season's pairings
+#[server]
+pub async fn load_pair_stats(pool: ExchangePool) -> Result<PairStats, ServerFnError> {
+    as_organizer(|_, season| {
+        let mut history = vault::load_pairings(storage::open_default()?.as_ref())?;
+        history.retain(|pairing| pairing.year < season);
+        Ok(PairStats::from_history(&history, Some(pool)))
+    })
+}
+
+/// Runs `action` for the signed-in organizer in the season today belongs to,
+/// failing if nobody is signed in
+///
+/// The season is resolved on the server so `CHRISTMAS_SEASON_CUTOVER` only needs setting there.
+#[cfg(feature = "server")]
+fn as_organizer<T>(action: impl FnOnce(&str, i32) -> anyhow::Result<T>) -> Result<T, ServerFnError> {
+    let organizer = session_token()
+        .and_then(|token| organizer::session_organizer(&token))
+        .ok_or_else(|| ServerFnError::new("Sign in as an organizer first."))?;
+    season::current_season()
+        .and_then(|season| action(&organizer, season))
+        .map_err(server_error)
+}
+
+/// The session token from this request's cookie, if it has one
+#[cfg(feature = "server")]
+fn session_token() -> Option<String> {
+    let context = server_context();
+    let request = context.request_parts();
+    request
+        .headers
+        .get_all("cookie")
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token.to_string())
+}
+
+/// Sets the session cookie on the response, or clears it when `max_age` is 0
+///
+/// The cookie can't be read by scripts or sent from other sites, and only goes
+/// over HTTPS when the UI is served from an https `CHRISTMAS_BASE_URL`.
+#[cfg(feature = "server")]
+fn set_session_cookie(token: &str, max_age: u64) -> anyhow::Result<()> {
+    use server_fn::axum_export::http::{header::SET_COOKIE, HeaderValue};
+
+    let secure = std::env::var(reveal::BASE_URL_ENV).is_ok_and(|url| url.starts_with("https://"));
+    let cookie = format!(
+        "{SESSION_COOKIE}={token}; Max-Age={max_age}; Path=/; HttpOnly; SameSite=Strict{}",
+        if secure { "; Secure" } else { "" }
+    );
+    server_context()
+        .response_parts_mut()
+        .headers
+        .append(SET_COOKIE, HeaderValue::from_str(&cookie)?);
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+fn server_error(e: anyhow::Error) -> ServerFnError {
+    ServerFnError::new(format!("{e:#}"))
+}
+
+#[cfg(feature = "server")]
+fn generate_exchange(pool: ExchangePool, season: i32, organizer: &str) -> anyhow::Result<ExchangeResult> {
+    let mut storage = storage::open_default()?;
+    let exchange = draw::draw_exchange(storage.as_mut(), pool, season, organizer, &[])?;
+    let givers = exchange.into_iter().map(|(giver, _)| giver).collect();
+    exchange_result(storage.as_mut(), pool, season, givers)
+}
+
+#[cfg(feature = "server")]
+fn load_saved(pool: ExchangePool, season: i32) -> anyhow::Result<Option<ExchangeResult>> {
+    let mut storage = storage::open_default()?;
+    let givers = vault::givers(&storage.load_pairings()?, pool, season);
+    if givers.is_empty() {
+        return Ok(None);
+    }
+    exchange_result(storage.as_mut(), pool, season, givers).map(Some)
+}
+
+#[cfg(feature = "server")]
+fn exchange_result(
+    storage: &mut dyn Storage,
+    pool: ExchangePool,
+    year: i32,
+    mut givers: Vec<String>,
+) -> anyhow::Result<ExchangeResult> {
+    givers.sort();
+
+    Ok(ExchangeResult {
+        givers,
+        theme: theme_for_pool(storage, pool, year),
+        year,
+        status: storage.exchange_status(pool, year)?,
+        blind: blind::is_sealed(year)?,
+    })
+}
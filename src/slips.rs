@@ -0,0 +1,163 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+
+use crate::{
+    data,
+    giftexchange::ExchangePool,
+    history, qr, reveal,
+    storage::Storage,
+    themes::GiftTheme,
+    utils,
+    vault::{self, VaultKey},
+};
+
+/// What's printed on one giver's slip
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slip {
+    pub giver: String,
+    pub receiver: String,
+    pub theme: GiftTheme,
+    pub budget: Option<&'static str>,
+    pub deadline: Option<NaiveDate>,
+    pub exchange_date: Option<NaiveDate>,
+    /// The giver's reveal link, and an SVG QR code that opens it
+    pub reveal_url: String,
+    pub qr_svg: String,
+}
+
+/// A slip for every giver in the saved draw for `pool` in `year`, sorted by giver
+///
+/// Each slip carries the giver's reveal link, issuing links if there are none yet.
+pub fn slips(storage: &mut dyn Storage, key: &VaultKey, pool: ExchangePool, year: i32) -> Result<Vec<Slip>> {
+    let mut pairings = history::pool_pairings(&vault::decrypt_pairings(key, &storage.load_pairings()?)?, pool, year);
+    if pairings.is_empty() {
+        anyhow::bail!("There is no saved {pool} draw for {year} to print slips for.");
+    }
+    pairings.sort();
+
+    let tokens = reveal::current_tokens(storage, key, pool, year)?;
+    let theme = utils::theme_for_pool(storage, pool, year);
+    pairings
+        .into_iter()
+        .map(|(giver, receiver)| {
+            let token = tokens
+                .iter()
+                .find(|(name, _)| *name == giver)
+                .map(|(_, token)| token)
+                .with_context(|| format!("{giver} has no reveal link"))?;
+            let reveal_url = reveal::reveal_url(token);
+            Ok(Slip {
+                qr_svg: qr::svg(&reveal_url)?,
+                reveal_url,
+                giver,
+                receiver,
+                theme: theme.clone(),
+                budget: data::budget_for_pool(pool),
+                deadline: data::deadline_for_pool(pool, year),
+                exchange_date: data::exchange_date_for_pool(pool, year),
+            })
+        })
+        .collect()
+}
+
+/// Styles for the slips, shared by the standalone page and the web UI
+///
+/// Each slip is cut out along its dashed border and folded along the dotted
+/// line, so the half with the receiver ends up inside.
+const STYLE: &str = "
+.slips { display: grid; grid-template-columns: repeat(2, 1fr); gap: 0.5rem; font-family: Georgia, serif; color: #1f2937; }
+.slip { border: 1px dashed #9ca3af; break-inside: avoid; page-break-inside: avoid; }
+.slip-outside, .slip-inside { box-sizing: border-box; padding: 0.75rem; text-align: center; display: flex; flex-direction: column; justify-content: center; }
+.slip-outside { height: 6.5cm; background: repeating-linear-gradient(45deg, #fef2f2, #fef2f2 6px, #f0fdf4 6px, #f0fdf4 12px); }
+.slip-outside .to { font-size: 1.75rem; font-weight: bold; color: #166534; }
+.slip-outside .hint { font-size: 0.8rem; color: #6b7280; margin-top: 0.5rem; }
+.fold { border-top: 2px dotted #6b7280; position: relative; }
+.fold span { position: absolute; top: -0.6rem; left: 50%; transform: translateX(-50%); background: white; padding: 0 0.5rem; font-size: 0.7rem; color: #6b7280; }
+.slip-inside { min-height: 6.5cm; }
+.slip-inside .label { font-size: 0.9rem; color: #4b5563; }
+.slip-inside .receiver { font-size: 2rem; font-weight: bold; color: #991b1b; margin: 0.25rem 0 0.5rem; }
+.slip-inside .detail { font-size: 0.9rem; margin: 0.1rem 0; }
+.slip-inside .qr svg { width: 2.75cm; height: 2.75cm; margin-top: 0.5rem; }
+.slip-inside .qr-hint { font-size: 0.65rem; color: #6b7280; word-break: break-all; }
+@media print { .no-print { display: none; } body { margin: 0; } }
+";
+
+/// The slips as an HTML fragment, with their styles
+pub fn render(pool: ExchangePool, year: i32, slips: &[Slip]) -> String {
+    let mut html = format!("<style>{STYLE}</style>\n<div class=\"slips\">\n");
+    for slip in slips {
+        let mut details = vec![format!("{}: {}", slip.theme.kind.label(), slip.theme)];
+        if let Some(budget) = slip.budget {
+            details.push(format!("Budget: {budget}"));
+        }
+        if let Some(deadline) = slip.deadline {
+            details.push(format!("Buy by {}", deadline.format("%A, %B %-d")));
+        }
+        if let Some(date) = slip.exchange_date {
+            details.push(format!("Exchange on {}", date.format("%A, %B %-d")));
+        }
+        let details: String = details
+            .iter()
+            .map(|detail| format!("      <div class=\"detail\">{}</div>\n", escape(detail)))
+            .collect();
+
+        html.push_str(&format!(
+            "  <div class=\"slip\">\n    <div class=\"slip-outside\">\n      <div class=\"to\">🎄 {giver}</div>\n      <div class=\"hint\">{pool} {year} · Fold along the dotted line and don't peek</div>\n    </div>\n    <div class=\"fold\"><span>fold</span></div>\n    <div class=\"slip-inside\">\n      <div class=\"label\">{giver}, you're giving to</div>\n      <div class=\"receiver\">🎁 {receiver}</div>\n{details}      <div class=\"qr\">{qr}</div>\n      <div class=\"qr-hint\">Scan for your reveal page and messages, or visit {url}</div>\n    </div>\n  </div>\n",
+            giver = escape(&slip.giver),
+            receiver = escape(&slip.receiver),
+            qr = slip.qr_svg,
+            url = escape(&slip.reveal_url),
+            pool = escape(&pool.to_string()),
+        ));
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+/// The slips as a standalone page, ready to print or save as a PDF
+pub fn document(pool: ExchangePool, year: i32, slips: &[Slip]) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} {year} gift slips</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(&pool.to_string()),
+        render(pool, year, slips)
+    )
+}
+
+/// `text` with the characters HTML treats specially escaped
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes::ThemeKind;
+
+    #[test]
+    fn names_are_escaped() {
+        let slip = Slip {
+            giver: "<script>alert(1)</script>".to_string(),
+            receiver: "Tom & \"Jerry\"".to_string(),
+            theme: GiftTheme {
+                kind: ThemeKind::Letter,
+                value: "<b>".to_string(),
+            },
+            budget: Some("$20 & under"),
+            deadline: None,
+            exchange_date: None,
+            reveal_url: "http://localhost:8080/reveal/abc?a=1&b=2".to_string(),
+            qr_svg: "<svg></svg>".to_string(),
+        };
+        let html = render(ExchangePool::Pets, 2025, &[slip]);
+        assert!(!html.contains("<script>") && !html.contains("<b>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains("Tom &amp; &quot;Jerry&quot;"));
+        assert!(html.contains("Budget: $20 &amp; under"));
+        assert!(html.contains("reveal/abc?a=1&amp;b=2"));
+        // The QR code is markup we drew ourselves, so it goes in as is
+        assert!(html.contains("<svg></svg>"));
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{giftexchange::ExchangePool, history::HistoricalPairing};
+
+/// How spread out one giver's draws have been across receivers
+#[derive(Debug, Clone, PartialEq)]
+pub struct GiverConcentration {
+    pub giver: String,
+    pub draws: u32,
+    pub distinct_receivers: usize,
+    /// Herfindahl index of the giver's receivers: 1.0 means they always drew
+    /// the same person, 1/n means their draws were spread evenly over n people
+    pub concentration: f64,
+}
+
+/// How often each giver → receiver pair has happened across the saved years
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PairStats {
+    /// Everyone who has given or received, sorted by name
+    pub people: Vec<String>,
+    pub years: Vec<i32>,
+    #[serde(with = "pair_counts")]
+    counts: HashMap<(String, String), u32>,
+}
+
+/// JSON object keys must be strings, so pair counts are sent to the web UI as a list
+mod pair_counts {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(counts: &HashMap<(String, String), u32>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(counts)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<(String, String), u32>, D::Error> {
+        Ok(Vec::<((String, String), u32)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl PairStats {
+    /// Counts pairings in `history`, optionally restricted to one pool
+    pub fn from_history(history: &[HistoricalPairing], pool: Option<ExchangePool>) -> Self {
+        let mut people = BTreeSet::new();
+        let mut years = BTreeSet::new();
+        let mut counts = HashMap::new();
+
+        for pairing in history.iter().filter(|p| pool.is_none_or(|pool| p.pool == pool)) {
+            people.insert(pairing.giver.clone());
+            people.insert(pairing.receiver.clone());
+            years.insert(pairing.year);
+            *counts
+                .entry((pairing.giver.clone(), pairing.receiver.clone()))
+                .or_insert(0) += 1;
+        }
+
+        PairStats {
+            people: people.into_iter().collect(),
+            years: years.into_iter().collect(),
+            counts,
+        }
+    }
+
+    /// Number of times `giver` has drawn `receiver`
+    pub fn count(&self, giver: &str, receiver: &str) -> u32 {
+        self.counts
+            .get(&(giver.to_string(), receiver.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The most times any single pair has happened
+    pub fn max_count(&self) -> u32 {
+        self.counts.values().copied().max().unwrap_or(0)
+    }
+
+    /// Giver → receiver pairs that have never happened, sorted by giver
+    pub fn never_drawn(&self) -> Vec<(String, String)> {
+        let mut pairs = vec![];
+        for giver in &self.people {
+            for receiver in &self.people {
+                if giver != receiver && self.count(giver, receiver) == 0 {
+                    pairs.push((giver.clone(), receiver.clone()));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// How concentrated each giver's draws have been, most concentrated first
+    pub fn concentration(&self) -> Vec<GiverConcentration> {
+        let mut result: Vec<GiverConcentration> = self
+            .people
+            .iter()
+            .filter_map(|giver| {
+                let counts: Vec<u32> = self
+                    .people
+                    .iter()
+                    .map(|receiver| self.count(giver, receiver))
+                    .filter(|count| *count > 0)
+                    .collect();
+                let draws: u32 = counts.iter().sum();
+                if draws == 0 {
+                    return None;
+                }
+                let concentration = counts
+                    .iter()
+                    .map(|count| (*count as f64 / draws as f64).powi(2))
+                    .sum();
+                Some(GiverConcentration {
+                    giver: giver.clone(),
+                    draws,
+                    distinct_receivers: counts.len(),
+                    concentration,
+                })
+            })
+            .collect();
+        result.sort_by(|a, b| {
+            b.concentration
+                .total_cmp(&a.concentration)
+                .then_with(|| a.giver.cmp(&b.giver))
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairing(year: i32, giver: &str, receiver: &str) -> HistoricalPairing {
+        HistoricalPairing {
+            year,
+            pool: ExchangePool::IslandLife,
+            giver: giver.to_string(),
+            receiver: receiver.to_string(),
+        }
+    }
+
+    /// Alec drew Will every year; Will drew Kaylee, Alec and Grace once each
+    fn stats() -> PairStats {
+        PairStats::from_history(
+            &[
+                pairing(2022, "Alec", "Will"),
+                pairing(2022, "Will", "Kaylee"),
+                pairing(2023, "Alec", "Will"),
+                pairing(2023, "Will", "Alec"),
+                pairing(2024, "Alec", "Will"),
+                pairing(2024, "Will", "Grace"),
+            ],
+            None,
+        )
+    }
+
+    #[test]
+    fn concentration_is_one_for_a_single_receiver_and_one_over_n_when_even() {
+        let concentration = stats().concentration();
+        assert_eq!(concentration.len(), 2);
+        assert_eq!(
+            concentration[0],
+            GiverConcentration {
+                giver: "Alec".to_string(),
+                draws: 3,
+                distinct_receivers: 1,
+                concentration: 1.0,
+            }
+        );
+        assert_eq!(concentration[1].giver, "Will");
+        assert_eq!(concentration[1].distinct_receivers, 3);
+        assert!((concentration[1].concentration - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn never_drawn_leaves_out_drawn_pairs_and_self_pairs() {
+        let never = stats().never_drawn();
+        assert!(!never.iter().any(|(giver, receiver)| giver == receiver));
+        assert!(!never.contains(&("Alec".to_string(), "Will".to_string())));
+        assert!(never.contains(&("Alec".to_string(), "Kaylee".to_string())));
+        // 4 people make 12 possible pairs, and 4 different ones have been drawn
+        assert_eq!(never.len(), 8);
+    }
+
+    #[test]
+    fn counts_can_be_limited_to_one_pool() {
+        let mut history = vec![pairing(2024, "Alec", "Will")];
+        history.push(HistoricalPairing {
+            pool: ExchangePool::Pets,
+            ..pairing(2024, "Alec", "Will")
+        });
+        assert_eq!(PairStats::from_history(&history, None).count("Alec", "Will"), 2);
+        let pets = PairStats::from_history(&history, Some(ExchangePool::Pets));
+        assert_eq!((pets.count("Alec", "Will"), pets.max_count()), (1, 1));
+        assert_eq!(pets.count("Will", "Alec"), 0);
+    }
+}
@@ -0,0 +1,463 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use super::Storage;
+use crate::{
+    audit::AuditEntry,
+    commitment::DrawCommitment,
+    exchange::{Exclusion, Participant},
+    giftexchange::ExchangePool,
+    lifecycle::{ExchangeStatus, Unlock},
+    messages::Message,
+    randomness::SeedContribution,
+    reminders::SentReminder,
+    reveal::RevealToken,
+    themes::ThemeHistory,
+    vault::EncryptedPairing,
+    wishlist::WishlistItem,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredData {
+    exchanges: Vec<StoredExchange>,
+    themes: Vec<StoredTheme>,
+    #[serde(default)]
+    audit: Vec<AuditEntry>,
+    #[serde(default)]
+    reveal_tokens: Vec<RevealToken>,
+    #[serde(default)]
+    commitments: Vec<DrawCommitment>,
+    #[serde(default)]
+    seed_contributions: Vec<SeedContribution>,
+    #[serde(default)]
+    messages: Vec<Message>,
+    #[serde(default)]
+    sent_reminders: Vec<SentReminder>,
+    #[serde(default)]
+    wishlists: Vec<StoredWishlist>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredWishlist {
+    owner: String,
+    items: Vec<WishlistItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredExchange {
+    pool: ExchangePool,
+    year: i32,
+    participants: Vec<StoredParticipant>,
+    pairings: Vec<StoredPairing>,
+    #[serde(default)]
+    status: ExchangeStatus,
+    #[serde(default)]
+    unlocks: Vec<Unlock>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredParticipant {
+    name: String,
+    exclusions: Vec<StoredExclusion>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredExclusion {
+    /// Files written before exclusions could be time-bounded list bare names
+    Name(String),
+    Rule(Exclusion),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredPairing {
+    giver: String,
+    /// Encrypted under the admin key, or a plain name in files written before that
+    receiver: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredTheme {
+    pool: ExchangePool,
+    year: i32,
+    value: String,
+}
+
+fn stored_pairings(pairings: &[EncryptedPairing]) -> Vec<StoredPairing> {
+    pairings
+        .iter()
+        .map(|p| StoredPairing {
+            giver: p.giver.clone(),
+            receiver: p.receiver.clone(),
+        })
+        .collect()
+}
+
+fn read(path: &Path) -> Result<StoredData> {
+    if !path.exists() {
+        return Ok(StoredData::default());
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Storage kept in a single JSON file such as `drawings.json`, for people who
+/// don't want a database
+pub struct JsonStorage {
+    path: PathBuf,
+    data: StoredData,
+    /// Set while `in_transaction` runs, so its writes are saved together at the end
+    in_transaction: bool,
+}
+
+impl JsonStorage {
+    /// Opens the file at `path`, treating a missing file as empty storage
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            data: read(path)?,
+            in_transaction: false,
+        })
+    }
+
+    /// Locks the file against other writers and reads it again, so a change
+    /// saved by another process since this one opened it isn't written over
+    ///
+    /// The lock is held until the returned file is dropped, after the write.
+    /// Inside a transaction, which already holds it, this does nothing.
+    fn reload(&mut self) -> Result<Option<File>> {
+        if self.in_transaction {
+            return Ok(None);
+        }
+        let lock_path = self.path.with_extension("json.lock");
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        lock.lock()
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+        self.data = read(&self.path)?;
+        Ok(Some(lock))
+    }
+
+    fn exchange(&self, pool: ExchangePool, year: i32) -> Option<&StoredExchange> {
+        self.data
+            .exchanges
+            .iter()
+            .find(|e| e.pool == pool && e.year == year)
+    }
+
+    fn exchange_mut(&mut self, pool: ExchangePool, year: i32) -> Result<&mut StoredExchange> {
+        self.data
+            .exchanges
+            .iter_mut()
+            .find(|e| e.pool == pool && e.year == year)
+            .with_context(|| format!("There is no saved {pool} exchange for {year}"))
+    }
+
+    /// Writes the whole file, replacing it atomically so a failed write can't truncate it
+    fn flush(&self) -> Result<()> {
+        if self.in_transaction {
+            return Ok(());
+        }
+        let contents = serde_json::to_string_pretty(&self.data)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, contents)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to replace {}", self.path.display()))
+    }
+}
+
+impl Storage for JsonStorage {
+    fn in_transaction(&mut self, writes: &mut dyn FnMut(&mut dyn Storage) -> Result<()>) -> Result<()> {
+        if self.in_transaction {
+            return writes(self);
+        }
+        let _lock = self.reload()?;
+        self.in_transaction = true;
+        let result = writes(self);
+        self.in_transaction = false;
+        match result {
+            Ok(()) => self.flush(),
+            Err(e) => {
+                // Drop the writes made before the failure
+                self.data = read(&self.path)?;
+                Err(e)
+            }
+        }
+    }
+
+    fn save_draw(
+        &mut self,
+        pool: ExchangePool,
+        year: i32,
+        participants: &[Participant],
+        pairings: &[EncryptedPairing],
+    ) -> Result<()> {
+        let _lock = self.reload()?;
+        let participants = participants
+            .iter()
+            .map(|p| StoredParticipant {
+                name: p.name.clone(),
+                exclusions: p.exclusions.iter().cloned().map(StoredExclusion::Rule).collect(),
+            })
+            .collect();
+        let pairings = stored_pairings(pairings);
+
+        // Keep the lifecycle of an existing exchange when its pairings are replaced
+        match self.exchange_mut(pool, year) {
+            Ok(stored) => {
+                stored.participants = participants;
+                stored.pairings = pairings;
+            }
+            Err(_) => {
+                self.data.exchanges.push(StoredExchange {
+                    pool,
+                    year,
+                    participants,
+                    pairings,
+                    status: ExchangeStatus::Draft,
+                    unlocks: vec![],
+                });
+                self.data.exchanges.sort_by_key(|e| (e.year, e.pool.slug()));
+            }
+        }
+        self.flush()
+    }
+
+    fn replace_pairings(&mut self, pool: ExchangePool, year: i32, pairings: &[EncryptedPairing]) -> Result<()> {
+        let _lock = self.reload()?;
+        self.exchange_mut(pool, year)?.pairings = stored_pairings(pairings);
+        self.flush()
+    }
+
+    fn load_pairings(&self) -> Result<Vec<EncryptedPairing>> {
+        Ok(self
+            .data
+            .exchanges
+            .iter()
+            .flat_map(|e| {
+                e.pairings.iter().map(|p| EncryptedPairing {
+                    year: e.year,
+                    pool: e.pool,
+                    giver: p.giver.clone(),
+                    receiver: p.receiver.clone(),
+                })
+            })
+            .collect())
+    }
+
+    fn load_themes(&self) -> Result<ThemeHistory> {
+        let mut history = ThemeHistory::default();
+        for theme in &self.data.themes {
+            history.record(theme.pool, theme.year, &theme.value);
+        }
+        Ok(history)
+    }
+
+    fn save_theme(&mut self, pool: ExchangePool, year: i32, value: &str) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data
+            .themes
+            .retain(|t| !(t.pool == pool && t.year == year));
+        self.data.themes.push(StoredTheme {
+            pool,
+            year,
+            value: value.to_string(),
+        });
+        self.flush()
+    }
+
+    fn exchange_status(&self, pool: ExchangePool, year: i32) -> Result<ExchangeStatus> {
+        Ok(self
+            .exchange(pool, year)
+            .map(|e| e.status)
+            .unwrap_or_default())
+    }
+
+    fn set_exchange_status(
+        &mut self,
+        pool: ExchangePool,
+        year: i32,
+        status: ExchangeStatus,
+    ) -> Result<()> {
+        let _lock = self.reload()?;
+        self.exchange_mut(pool, year)?.status = status;
+        self.flush()
+    }
+
+    fn record_unlock(&mut self, pool: ExchangePool, year: i32, unlock: &Unlock) -> Result<()> {
+        let _lock = self.reload()?;
+        self.exchange_mut(pool, year)?.unlocks.push(unlock.clone());
+        self.flush()
+    }
+
+    fn load_unlocks(&self, pool: ExchangePool, year: i32) -> Result<Vec<Unlock>> {
+        Ok(self
+            .exchange(pool, year)
+            .map(|e| e.unlocks.clone())
+            .unwrap_or_default())
+    }
+
+    fn append_audit(&mut self, entry: &AuditEntry) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data.audit.push(entry.clone());
+        self.flush()
+    }
+
+    fn load_audit(&self) -> Result<Vec<AuditEntry>> {
+        Ok(self.data.audit.clone())
+    }
+
+    fn save_reveal_tokens(
+        &mut self,
+        pool: ExchangePool,
+        year: i32,
+        tokens: &[RevealToken],
+    ) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data
+            .reveal_tokens
+            .retain(|t| !(t.pool == pool && t.year == year));
+        self.data.reveal_tokens.extend_from_slice(tokens);
+        self.flush()
+    }
+
+    fn save_commitment(&mut self, commitment: &DrawCommitment) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data
+            .commitments
+            .retain(|c| !(c.pool == commitment.pool && c.year == commitment.year));
+        self.data.commitments.push(commitment.clone());
+        self.flush()
+    }
+
+    fn load_commitment(&self, pool: ExchangePool, year: i32) -> Result<Option<DrawCommitment>> {
+        Ok(self
+            .data
+            .commitments
+            .iter()
+            .find(|c| c.pool == pool && c.year == year)
+            .cloned())
+    }
+
+    fn save_seed_contributions(
+        &mut self,
+        pool: ExchangePool,
+        year: i32,
+        contributions: &[SeedContribution],
+    ) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data
+            .seed_contributions
+            .retain(|c| !(c.pool == pool && c.year == year));
+        self.data.seed_contributions.extend_from_slice(contributions);
+        self.flush()
+    }
+
+    fn load_seed_contributions(&self, pool: ExchangePool, year: i32) -> Result<Vec<SeedContribution>> {
+        Ok(self
+            .data
+            .seed_contributions
+            .iter()
+            .filter(|c| c.pool == pool && c.year == year)
+            .cloned()
+            .collect())
+    }
+
+    fn append_message(&mut self, message: &Message) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data.messages.push(message.clone());
+        self.flush()
+    }
+
+    fn load_messages(&self, pool: ExchangePool, year: i32) -> Result<Vec<Message>> {
+        Ok(self
+            .data
+            .messages
+            .iter()
+            .filter(|m| m.pool == pool && m.year == year)
+            .cloned()
+            .collect())
+    }
+
+    fn clear_messages(&mut self, pool: ExchangePool, year: i32) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data.messages.retain(|m| !(m.pool == pool && m.year == year));
+        self.flush()
+    }
+
+    fn record_reminder(&mut self, reminder: &SentReminder) -> Result<bool> {
+        let _lock = self.reload()?;
+        if self.data.sent_reminders.iter().any(|r| r.is_for(reminder)) {
+            return Ok(false);
+        }
+        self.data.sent_reminders.push(reminder.clone());
+        self.flush()?;
+        Ok(true)
+    }
+
+    fn forget_reminder(&mut self, reminder: &SentReminder) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data.sent_reminders.retain(|r| !r.is_for(reminder));
+        self.flush()
+    }
+
+    fn load_sent_reminders(&self, pool: ExchangePool, year: i32) -> Result<Vec<SentReminder>> {
+        Ok(self
+            .data
+            .sent_reminders
+            .iter()
+            .filter(|r| r.pool == pool && r.year == year)
+            .cloned()
+            .collect())
+    }
+
+    fn save_wishlist(&mut self, owner: &str, items: &[WishlistItem]) -> Result<()> {
+        let _lock = self.reload()?;
+        self.data.wishlists.retain(|w| w.owner != owner);
+        if !items.is_empty() {
+            self.data.wishlists.push(StoredWishlist {
+                owner: owner.to_string(),
+                items: items.to_vec(),
+            });
+        }
+        self.flush()
+    }
+
+    fn load_wishlist(&self, owner: &str) -> Result<Vec<WishlistItem>> {
+        Ok(self
+            .data
+            .wishlists
+            .iter()
+            .find(|w| w.owner == owner)
+            .map(|w| w.items.clone())
+            .unwrap_or_default())
+    }
+
+    fn load_reveal_tokens(&self, pool: ExchangePool, year: i32) -> Result<Vec<RevealToken>> {
+        Ok(self
+            .data
+            .reveal_tokens
+            .iter()
+            .filter(|t| t.pool == pool && t.year == year)
+            .cloned()
+            .collect())
+    }
+
+    fn find_reveal_token(&self, token_hash: &str) -> Result<Option<RevealToken>> {
+        Ok(self
+            .data
+            .reveal_tokens
+            .iter()
+            .find(|t| t.token_hash == token_hash)
+            .cloned())
+    }
+}
@@ -0,0 +1,375 @@
+mod json;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::{
+    audit::AuditEntry,
+    commitment::DrawCommitment,
+    exchange::Participant,
+    giftexchange::ExchangePool,
+    lifecycle::{ExchangeStatus, Unlock},
+    messages::Message,
+    randomness::SeedContribution,
+    reminders::SentReminder,
+    reveal::RevealToken,
+    themes::ThemeHistory,
+    vault::EncryptedPairing,
+    wishlist::WishlistItem,
+};
+
+pub use json::JsonStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+/// Environment variable that overrides where draws are stored
+///
+/// Paths ending in `.json` use the flat-file backend, anything else SQLite.
+pub const STORAGE_ENV: &str = "CHRISTMAS_STORAGE";
+
+/// Where draws are stored when `CHRISTMAS_STORAGE` isn't set
+#[cfg(feature = "sqlite")]
+pub const DEFAULT_STORAGE_PATH: &str = "drawings.db";
+#[cfg(not(feature = "sqlite"))]
+pub const DEFAULT_STORAGE_PATH: &str = "drawings.json";
+
+/// Persistent record of rosters, draws, pairings and themes
+pub trait Storage {
+    /// Runs `writes`, saving every change it makes or, if it fails, none of them
+    fn in_transaction(&mut self, writes: &mut dyn FnMut(&mut dyn Storage) -> Result<()>) -> Result<()>;
+
+    /// Records a draw for `pool` in `year`: the roster with its exclusions and
+    /// the encrypted pairings. Any pairings saved earlier for the same exchange are replaced.
+    fn save_draw(
+        &mut self,
+        pool: ExchangePool,
+        year: i32,
+        participants: &[Participant],
+        pairings: &[EncryptedPairing],
+    ) -> Result<()>;
+
+    /// Replaces the saved pairings for `pool` in `year`, keeping its roster
+    fn replace_pairings(&mut self, pool: ExchangePool, year: i32, pairings: &[EncryptedPairing]) -> Result<()>;
+
+    /// Every saved pairing across all years and pools, oldest first, as stored
+    fn load_pairings(&self) -> Result<Vec<EncryptedPairing>>;
+
+    /// Every theme drawn so far
+    fn load_themes(&self) -> Result<ThemeHistory>;
+
+    /// Records the theme drawn for `pool` in `year`
+    fn save_theme(&mut self, pool: ExchangePool, year: i32, value: &str) -> Result<()>;
+
+    /// Lifecycle status of the exchange for `pool` in `year`; `Draft` if it hasn't been drawn
+    fn exchange_status(&self, pool: ExchangePool, year: i32) -> Result<ExchangeStatus>;
+
+    fn set_exchange_status(&mut self, pool: ExchangePool, year: i32, status: ExchangeStatus) -> Result<()>;
+
+    /// Records an admin unlocking the exchange for `pool` in `year`
+    fn record_unlock(&mut self, pool: ExchangePool, year: i32, unlock: &Unlock) -> Result<()>;
+
+    /// Every unlock of the exchange for `pool` in `year`, oldest first
+    fn load_unlocks(&self, pool: ExchangePool, year: i32) -> Result<Vec<Unlock>>;
+
+    fn append_audit(&mut self, entry: &AuditEntry) -> Result<()>;
+
+    /// The whole audit log, oldest first
+    fn load_audit(&self) -> Result<Vec<AuditEntry>>;
+
+    /// Replaces the reveal tokens issued for the exchange for `pool` in `year`
+    fn save_reveal_tokens(&mut self, pool: ExchangePool, year: i32, tokens: &[RevealToken]) -> Result<()>;
+
+    /// Replaces the commitment saved for the draw it belongs to
+    fn save_commitment(&mut self, commitment: &DrawCommitment) -> Result<()>;
+
+    /// The commitment to the saved draw for `pool` in `year`, if it was drawn with one
+    fn load_commitment(&self, pool: ExchangePool, year: i32) -> Result<Option<DrawCommitment>>;
+
+    /// Replaces the joint seed contributions for the draw of `pool` in `year`
+    fn save_seed_contributions(&mut self, pool: ExchangePool, year: i32, contributions: &[SeedContribution]) -> Result<()>;
+
+    /// Joint seed contributions for the draw of `pool` in `year`, in the order they were committed
+    fn load_seed_contributions(&self, pool: ExchangePool, year: i32) -> Result<Vec<SeedContribution>>;
+
+    fn append_message(&mut self, message: &Message) -> Result<()>;
+
+    /// Every message posted in the exchange for `pool` in `year`, oldest first
+    fn load_messages(&self, pool: ExchangePool, year: i32) -> Result<Vec<Message>>;
+
+    /// Deletes every message posted in the exchange for `pool` in `year`
+    fn clear_messages(&mut self, pool: ExchangePool, year: i32) -> Result<()>;
+
+    /// Records a reminder as sent, so it isn't sent again, returning false if
+    /// it was already recorded
+    ///
+    /// Reminders are recorded before they're sent, so of two overlapping runs
+    /// only the one that recorded a reminder sends it.
+    fn record_reminder(&mut self, reminder: &SentReminder) -> Result<bool>;
+
+    /// Removes the record of a reminder that couldn't be sent, so it's tried again
+    fn forget_reminder(&mut self, reminder: &SentReminder) -> Result<()>;
+
+    /// Every reminder sent for the exchange for `pool` in `year`, oldest first
+    fn load_sent_reminders(&self, pool: ExchangePool, year: i32) -> Result<Vec<SentReminder>>;
+
+    /// Replaces `owner`'s wishlist
+    ///
+    /// Wishlists are kept per person rather than per exchange, so one list
+    /// serves every pool and season they're in.
+    fn save_wishlist(&mut self, owner: &str, items: &[WishlistItem]) -> Result<()>;
+
+    /// `owner`'s wishlist, in the order it was saved
+    fn load_wishlist(&self, owner: &str) -> Result<Vec<WishlistItem>>;
+
+    /// The reveal tokens issued for the exchange for `pool` in `year`
+    fn load_reveal_tokens(&self, pool: ExchangePool, year: i32) -> Result<Vec<RevealToken>>;
+
+    /// The reveal token whose hash is `token_hash`, if one was issued
+    fn find_reveal_token(&self, token_hash: &str) -> Result<Option<RevealToken>>;
+}
+
+/// Opens the storage at `path`, choosing the backend from its extension
+pub fn open(path: &Path) -> Result<Box<dyn Storage>> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        return Ok(Box::new(JsonStorage::open(path)?));
+    }
+
+    #[cfg(feature = "sqlite")]
+    return Ok(Box::new(SqliteStorage::open(path)?));
+
+    #[cfg(not(feature = "sqlite"))]
+    anyhow::bail!(
+        "{} looks like a SQLite database, which needs the sqlite feature. Use a .json path instead.",
+        path.display()
+    )
+}
+
+/// Opens the storage named by `CHRISTMAS_STORAGE`, or the default one
+pub fn open_default() -> Result<Box<dyn Storage>> {
+    let path = std::env::var(STORAGE_ENV).unwrap_or_else(|_| DEFAULT_STORAGE_PATH.to_string());
+    open(Path::new(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        audit::AuditAction,
+        reminders::{ReminderKind, SentReminder},
+        testing::{roster, TempPath},
+    };
+
+    fn pairing(pool: ExchangePool, year: i32, giver: &str, receiver: &str) -> EncryptedPairing {
+        EncryptedPairing {
+            year,
+            pool,
+            giver: giver.to_string(),
+            receiver: receiver.to_string(),
+        }
+    }
+
+    fn save_draw_round_trip(storage: &mut dyn Storage) {
+        let first = vec![
+            pairing(ExchangePool::IslandLife, 2024, "Alec", "Will"),
+            pairing(ExchangePool::IslandLife, 2024, "Will", "Kaylee"),
+            pairing(ExchangePool::IslandLife, 2024, "Kaylee", "Alec"),
+        ];
+        storage.save_draw(ExchangePool::IslandLife, 2024, &roster(), &first).unwrap();
+        assert_eq!(storage.load_pairings().unwrap(), first);
+
+        // Saving the same exchange again replaces its pairings rather than adding to them
+        let second = vec![
+            pairing(ExchangePool::IslandLife, 2024, "Alec", "Kaylee"),
+            pairing(ExchangePool::IslandLife, 2024, "Kaylee", "Will"),
+            pairing(ExchangePool::IslandLife, 2024, "Will", "Alec"),
+        ];
+        storage.save_draw(ExchangePool::IslandLife, 2024, &roster(), &second).unwrap();
+        assert_eq!(storage.load_pairings().unwrap(), second);
+
+        let third = vec![
+            pairing(ExchangePool::IslandLife, 2024, "Alec", "Will"),
+            pairing(ExchangePool::IslandLife, 2024, "Kaylee", "Alec"),
+            pairing(ExchangePool::IslandLife, 2024, "Will", "Kaylee"),
+        ];
+        storage.replace_pairings(ExchangePool::IslandLife, 2024, &third).unwrap();
+        assert_eq!(storage.load_pairings().unwrap(), third);
+    }
+
+    fn status_round_trip(storage: &mut dyn Storage) {
+        assert_eq!(storage.exchange_status(ExchangePool::IslandLife, 2024).unwrap(), ExchangeStatus::Draft);
+        storage.save_draw(ExchangePool::IslandLife, 2024, &roster(), &[]).unwrap();
+        storage
+            .set_exchange_status(ExchangePool::IslandLife, 2024, ExchangeStatus::Finalized)
+            .unwrap();
+        assert_eq!(storage.exchange_status(ExchangePool::IslandLife, 2024).unwrap(), ExchangeStatus::Finalized);
+
+        // Redrawing keeps the exchange's status
+        storage.save_draw(ExchangePool::IslandLife, 2024, &roster(), &[]).unwrap();
+        assert_eq!(storage.exchange_status(ExchangePool::IslandLife, 2024).unwrap(), ExchangeStatus::Finalized);
+        assert_eq!(storage.exchange_status(ExchangePool::Grabergishimazureson, 2024).unwrap(), ExchangeStatus::Draft);
+    }
+
+    fn audit_round_trip(storage: &mut dyn Storage) {
+        assert_eq!(storage.load_audit().unwrap(), vec![]);
+        let draw = AuditEntry {
+            seed: Some("42".to_string()),
+            result_hash: Some("abc123".to_string()),
+            ..AuditEntry::new("alec", AuditAction::Draw, ExchangePool::IslandLife, 2024)
+        };
+        let unlock = AuditEntry {
+            detail: Some("Will joined late".to_string()),
+            ..AuditEntry::new("kaylee", AuditAction::Unlock, ExchangePool::IslandLife, 2024)
+        };
+        storage.append_audit(&draw).unwrap();
+        storage.append_audit(&unlock).unwrap();
+        assert_eq!(storage.load_audit().unwrap(), vec![draw, unlock]);
+    }
+
+    fn transaction_round_trip(storage: &mut dyn Storage) {
+        let draw = AuditEntry::new("alec", AuditAction::Draw, ExchangePool::IslandLife, 2024);
+        let regenerate = AuditEntry::new("alec", AuditAction::Regenerate, ExchangePool::IslandLife, 2024);
+        storage
+            .in_transaction(&mut |storage| {
+                storage.save_draw(ExchangePool::IslandLife, 2024, &roster(), &[])?;
+                storage.append_audit(&draw)
+            })
+            .unwrap();
+
+        // A failure part way undoes the writes made before it
+        let failed = storage.in_transaction(&mut |storage| {
+            storage.append_audit(&regenerate)?;
+            storage.set_exchange_status(ExchangePool::IslandLife, 2024, ExchangeStatus::Finalized)?;
+            anyhow::bail!("the reveal links couldn't be re-encrypted")
+        });
+        assert!(failed.is_err());
+        assert_eq!(storage.load_audit().unwrap(), vec![draw]);
+        assert_eq!(storage.exchange_status(ExchangePool::IslandLife, 2024).unwrap(), ExchangeStatus::Draft);
+    }
+
+    fn reminder_claims(storage: &mut dyn Storage) {
+        let reminder = SentReminder {
+            pool: ExchangePool::IslandLife,
+            year: 2024,
+            kind: ReminderKind::Deadline,
+            days_before: 7,
+            sent_at: "2024-12-01T09:00:00+00:00".to_string(),
+        };
+        assert!(storage.record_reminder(&reminder).unwrap());
+        // A second run finds it already claimed
+        assert!(!storage.record_reminder(&reminder).unwrap());
+        assert_eq!(storage.load_sent_reminders(ExchangePool::IslandLife, 2024).unwrap(), vec![reminder.clone()]);
+
+        storage.forget_reminder(&reminder).unwrap();
+        assert_eq!(storage.load_sent_reminders(ExchangePool::IslandLife, 2024).unwrap(), vec![]);
+        assert!(storage.record_reminder(&reminder).unwrap());
+    }
+
+    #[test]
+    fn json_save_draw_round_trip() {
+        let path = TempPath::new("save-draw");
+        save_draw_round_trip(&mut path.storage());
+    }
+
+    #[test]
+    fn json_status_round_trip() {
+        let path = TempPath::new("status");
+        status_round_trip(&mut path.storage());
+    }
+
+    #[test]
+    fn json_audit_round_trip() {
+        let path = TempPath::new("audit");
+        audit_round_trip(&mut path.storage());
+    }
+
+    #[test]
+    fn json_reopens_what_it_saved() {
+        let path = TempPath::new("reopen");
+        let mut storage = path.storage();
+        save_draw_round_trip(&mut storage);
+        let reopened = path.storage();
+        assert_eq!(reopened.load_pairings().unwrap(), storage.load_pairings().unwrap());
+    }
+
+    #[test]
+    fn json_transaction_round_trip() {
+        let path = TempPath::new("transaction");
+        transaction_round_trip(&mut path.storage());
+        assert_eq!(path.storage().load_audit().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn json_reminder_claims() {
+        let path = TempPath::new("reminders");
+        reminder_claims(&mut path.storage());
+    }
+
+    #[test]
+    fn json_keeps_writes_made_since_it_was_opened() {
+        let path = TempPath::new("concurrent");
+        let mut scheduler = path.storage();
+        let mut cli = path.storage();
+        let finalize = AuditEntry::new("alec", AuditAction::Finalize, ExchangePool::IslandLife, 2024);
+        let reveal = AuditEntry::new("kaylee", AuditAction::Reveal, ExchangePool::IslandLife, 2024);
+        cli.append_audit(&finalize).unwrap();
+        scheduler.append_audit(&reveal).unwrap();
+        assert_eq!(path.storage().load_audit().unwrap(), vec![finalize, reveal]);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_save_draw_round_trip() {
+        save_draw_round_trip(&mut SqliteStorage::open(Path::new(":memory:")).unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_status_round_trip() {
+        status_round_trip(&mut SqliteStorage::open(Path::new(":memory:")).unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_audit_round_trip() {
+        audit_round_trip(&mut SqliteStorage::open(Path::new(":memory:")).unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_reminder_claims() {
+        reminder_claims(&mut SqliteStorage::open(Path::new(":memory:")).unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_transaction_round_trip() {
+        transaction_round_trip(&mut SqliteStorage::open(Path::new(":memory:")).unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_keeps_every_reason_for_an_exclusion() {
+        use crate::exchange::{Exclusion, ExclusionReason};
+
+        let conn = sqlite::init_db(Path::new(":memory:")).unwrap();
+        let alec = roster()
+            .remove(0)
+            .excluding(Exclusion::in_years("Will", ExclusionReason::LastYear, &[2025]))
+            .excluding(Exclusion::in_years("Will", ExclusionReason::Request, &[2025]));
+        let alec_id = sqlite::add_participant(&conn, &alec, 2025).unwrap();
+        // Saving again replaces the exclusions rather than adding to them
+        sqlite::add_participant(&conn, &alec, 2025).unwrap();
+
+        let mut statement = conn
+            .prepare("SELECT reason FROM participant_exclusion WHERE participant_id = ?1 ORDER BY reason")
+            .unwrap();
+        let reasons: Vec<String> = statement
+            .query_map([alec_id], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(reasons, vec!["last-year", "request", "spouse"]);
+    }
+}
@@ -0,0 +1,979 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use rusqlite::{Connection, OptionalExtension};
+use rusqlite_migration::{Migrations, M};
+
+use super::Storage;
+use crate::{
+    audit::{AuditAction, AuditEntry},
+    commitment::DrawCommitment,
+    exchange::Participant,
+    giftexchange::ExchangePool,
+    lifecycle::{ExchangeStatus, Unlock},
+    messages::{Message, MessageSender},
+    randomness::{ContributionStatus, SeedContribution},
+    reminders::{ReminderKind, SentReminder},
+    reveal::RevealToken,
+    themes::ThemeHistory,
+    vault::EncryptedPairing,
+    wishlist::{Priority, WishlistItem},
+};
+
+pub fn init_db(path: &Path) -> Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    let migrations = Migrations::new(
+        vec![
+            M::up("
+                CREATE TABLE IF NOT EXISTS exchange (
+                    id INTEGER PRIMARY KEY,
+                    year INTEGER NOT NULL,
+                    name TEXT NOT NULL
+                )
+            "),
+            M::up("
+                CREATE TABLE IF NOT EXISTS participant (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL
+                )
+            "),
+            M::up("
+                CREATE TABLE IF NOT EXISTS participant_exchange (
+                    id INTEGER PRIMARY KEY,
+                    participant_id INTEGER NOT NULL,
+                    exchange_id INTEGER NOT NULL,
+                    FOREIGN KEY (participant_id) REFERENCES participant(id),
+                    FOREIGN KEY (exchange_id) REFERENCES exchange(id)
+                )
+            "),
+            M::up("CREATE TABLE IF NOT EXISTS participant_exclusion (
+                    id INTEGER PRIMARY KEY,
+                    participant_id INTEGER NOT NULL,
+                    excluded_participant_id INTEGER NOT NULL,
+                    year INTEGER DEFAULT NULL,
+                    FOREIGN KEY (participant_id) REFERENCES participant(id),
+                    FOREIGN KEY (excluded_participant_id) REFERENCES participant(id)
+                )
+            "),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS exchange_pairing (
+                    id INTEGER PRIMARY KEY,
+                    giver_id INTEGER NOT NULL,
+                    receiver_id INTEGER NOT NULL,
+                    exchange_id INTEGER NOT NULL,
+                    FOREIGN KEY (giver_id) REFERENCES participant(id),
+                    FOREIGN KEY (receiver_id) REFERENCES participant(id),
+                    FOREIGN KEY (exchange_id) REFERENCES exchange(id)
+                )"
+            ),
+            M::up(
+                "
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_participant_exchange ON participant_exchange (participant_id, exchange_id);
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_participant_exclusion ON participant_exclusion (participant_id, excluded_participant_id);
+                "
+            ),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS pool_theme (
+                    id INTEGER PRIMARY KEY,
+                    pool TEXT NOT NULL,
+                    year INTEGER NOT NULL,
+                    value TEXT NOT NULL,
+                    UNIQUE (pool, year)
+                )"
+            ),
+            M::up(
+                "
+                ALTER TABLE exchange ADD COLUMN status TEXT NOT NULL DEFAULT 'draft';
+                CREATE TABLE IF NOT EXISTS exchange_unlock (
+                    id INTEGER PRIMARY KEY,
+                    exchange_id INTEGER NOT NULL,
+                    unlocked_by TEXT NOT NULL,
+                    unlocked_at TEXT NOT NULL,
+                    reason TEXT NOT NULL,
+                    FOREIGN KEY (exchange_id) REFERENCES exchange(id)
+                );
+                "
+            ),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS audit_log (
+                    id INTEGER PRIMARY KEY,
+                    timestamp TEXT NOT NULL,
+                    actor TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    pool TEXT NOT NULL,
+                    year INTEGER NOT NULL,
+                    seed TEXT,
+                    result_hash TEXT,
+                    detail TEXT
+                )"
+            ),
+            // Earlier versions stamped every exclusion with the year it was
+            // first saved, but they all applied to every year
+            M::up(
+                "
+                UPDATE participant_exclusion SET year = NULL;
+                ALTER TABLE participant_exclusion ADD COLUMN until TEXT DEFAULT NULL;
+                DROP INDEX IF EXISTS idx_participant_exclusion;
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_participant_exclusion_year ON participant_exclusion (participant_id, excluded_participant_id, year);
+                "
+            ),
+            M::up("ALTER TABLE participant_exclusion ADD COLUMN reason TEXT NOT NULL DEFAULT 'request';"),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS reveal_token (
+                    id INTEGER PRIMARY KEY,
+                    pool TEXT NOT NULL,
+                    year INTEGER NOT NULL,
+                    giver TEXT NOT NULL,
+                    token_hash TEXT NOT NULL UNIQUE
+                )"
+            ),
+            // Receivers are stored encrypted, so the pairing keeps ciphertext
+            // rather than a participant id. Existing rows keep the receiver's
+            // name until `cargo run -- encrypt-history` encrypts them.
+            M::up(
+                "
+                CREATE TABLE exchange_pairing_encrypted (
+                    id INTEGER PRIMARY KEY,
+                    giver_id INTEGER NOT NULL,
+                    receiver TEXT NOT NULL,
+                    exchange_id INTEGER NOT NULL,
+                    FOREIGN KEY (giver_id) REFERENCES participant(id),
+                    FOREIGN KEY (exchange_id) REFERENCES exchange(id)
+                );
+                INSERT INTO exchange_pairing_encrypted (id, giver_id, receiver, exchange_id)
+                    SELECT ep.id, ep.giver_id, r.name, ep.exchange_id
+                    FROM exchange_pairing ep JOIN participant r ON r.id = ep.receiver_id;
+                DROP TABLE exchange_pairing;
+                ALTER TABLE exchange_pairing_encrypted RENAME TO exchange_pairing;
+                ALTER TABLE reveal_token ADD COLUMN encrypted_receiver TEXT NOT NULL DEFAULT '';
+                ALTER TABLE reveal_token ADD COLUMN encrypted_token TEXT NOT NULL DEFAULT '';
+                "
+            ),
+            M::up(
+                "
+                ALTER TABLE audit_log ADD COLUMN commitment TEXT;
+                CREATE TABLE IF NOT EXISTS draw_commitment (
+                    id INTEGER PRIMARY KEY,
+                    pool TEXT NOT NULL,
+                    year INTEGER NOT NULL,
+                    commitment TEXT NOT NULL,
+                    encrypted_opening TEXT NOT NULL,
+                    UNIQUE (pool, year)
+                );
+                "
+            ),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS seed_contribution (
+                    id INTEGER PRIMARY KEY,
+                    pool TEXT NOT NULL,
+                    year INTEGER NOT NULL,
+                    participant TEXT NOT NULL,
+                    commitment TEXT NOT NULL,
+                    value TEXT,
+                    status TEXT NOT NULL,
+                    committed_at TEXT NOT NULL,
+                    revealed_at TEXT,
+                    UNIQUE (pool, year, participant)
+                )"
+            ),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS message (
+                    id INTEGER PRIMARY KEY,
+                    pool TEXT NOT NULL,
+                    year INTEGER NOT NULL,
+                    recipient TEXT NOT NULL,
+                    sender TEXT NOT NULL,
+                    body TEXT NOT NULL,
+                    sent_on TEXT NOT NULL
+                )"
+            ),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS sent_reminder (
+                    id INTEGER PRIMARY KEY,
+                    pool TEXT NOT NULL,
+                    year INTEGER NOT NULL,
+                    kind TEXT NOT NULL,
+                    days_before INTEGER NOT NULL,
+                    sent_at TEXT NOT NULL,
+                    UNIQUE (pool, year, kind, days_before)
+                )"
+            ),
+            M::up(
+                "CREATE TABLE IF NOT EXISTS wishlist_item (
+                    id INTEGER PRIMARY KEY,
+                    owner TEXT NOT NULL,
+                    idea TEXT NOT NULL,
+                    link TEXT,
+                    price TEXT,
+                    priority TEXT NOT NULL,
+                    added_on TEXT NOT NULL
+                )"
+            ),
+            M::up("ALTER TABLE seed_contribution ADD COLUMN reveal_by TEXT"),
+            // The same person can be excluded in the same year for more than one reason
+            M::up(
+                "
+                DROP INDEX IF EXISTS idx_participant_exclusion_year;
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_participant_exclusion_reason
+                    ON participant_exclusion (participant_id, excluded_participant_id, year, reason);
+                "
+            ),
+        ]
+    );
+    migrations.to_latest(&mut conn)?;
+    Ok(conn)
+}
+
+/// Returns the id of the exchange for `pool` in `year`, creating it if needed
+fn ensure_exchange(conn: &Connection, pool: ExchangePool, year: i32) -> Result<i64> {
+    let exchange_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM exchange WHERE name = ?1 and year = ?2)",
+        (&pool.to_string(), &year),
+        |row| row.get(0),
+    )?;
+    if !exchange_exists {
+        conn.execute(
+            "INSERT INTO exchange (year, name) VALUES (?1, ?2)",
+            (&year, &pool.to_string()),
+        )?;
+    }
+    let exchange_id: i64 = conn.query_row(
+        "SELECT id FROM exchange WHERE name = ?1 and year = ?2",
+        (&pool.to_string(), &year),
+        |row| row.get(0),
+    )?;
+    Ok(exchange_id)
+}
+
+/// Returns the id of the exchange for `pool` in `year`, if it has been drawn
+fn find_exchange(conn: &Connection, pool: ExchangePool, year: i32) -> Result<Option<i64>> {
+    let exchange_id = conn
+        .query_row(
+            "SELECT id FROM exchange WHERE name = ?1 and year = ?2",
+            (&pool.to_string(), &year),
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(exchange_id)
+}
+
+/// Like `find_exchange`, but an error if the exchange hasn't been drawn
+fn existing_exchange(conn: &Connection, pool: ExchangePool, year: i32) -> Result<i64> {
+    find_exchange(conn, pool, year)?.with_context(|| format!("There is no saved {pool} exchange for {year}"))
+}
+
+/// Returns the id of the participant called `name`, creating it if needed
+fn ensure_participant(conn: &Connection, name: &str) -> Result<i64> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM participant WHERE name = ?1)",
+        [name],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute("INSERT INTO participant (name) VALUES (?1)", [name])?;
+    }
+    let participant_id: i64 = conn.query_row(
+        "SELECT id FROM participant WHERE name = ?1",
+        [name],
+        |row| row.get(0),
+    )?;
+    Ok(participant_id)
+}
+
+/// Links `participant` to this year's exchanges and replaces their saved exclusions
+///
+/// Exclusions limited to some seasons get one row per season in `year`; the
+/// rest have a NULL `year` and apply to every season until `until`, if set.
+pub fn add_participant(conn: &Connection, participant: &Participant, year: i32) -> Result<i64> {
+    let participant_id = ensure_participant(conn, &participant.name)?;
+
+    // Only link exchanges that have been created for this year
+    for exchange in &participant.exchange_pools {
+        if let Some(exchange_id) = find_exchange(conn, *exchange, year)? {
+            conn.execute(
+                "INSERT OR IGNORE INTO participant_exchange (participant_id, exchange_id) VALUES (?1, ?2)",
+                [&participant_id, &exchange_id],
+            )?;
+        }
+    }
+
+    conn.execute(
+        "DELETE FROM participant_exclusion WHERE participant_id = ?1",
+        [participant_id],
+    )?;
+    for exclusion in &participant.exclusions {
+        let excluded_participant_id = ensure_participant(conn, &exclusion.name)?;
+        let until = exclusion.until.map(|date| date.to_string());
+        let years: Vec<Option<i32>> = if exclusion.years.is_empty() {
+            vec![None]
+        } else {
+            exclusion.years.iter().copied().map(Some).collect()
+        };
+        for exclusion_year in years {
+            conn.execute(
+                "INSERT OR IGNORE INTO participant_exclusion (participant_id, excluded_participant_id, year, until, reason)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+                (&participant_id, &excluded_participant_id, &exclusion_year, &until, exclusion.reason.slug()),
+            )?;
+        }
+    }
+
+    Ok(participant_id)
+}
+
+pub fn reset_pairs_for_exchange(conn: &Connection, exchange_id: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM exchange_pairing WHERE exchange_id = ?1",
+        [exchange_id],
+    )?;
+    Ok(())
+}
+
+/// Saves a pairing whose receiver is already encrypted
+pub fn add_exchange_pair(
+    conn: &Connection,
+    giver_id: i64,
+    encrypted_receiver: &str,
+    exchange_id: i64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO exchange_pairing (giver_id, receiver, exchange_id) VALUES (?1, ?2, ?3)",
+        (giver_id, encrypted_receiver, exchange_id),
+    )?;
+    Ok(())
+}
+
+/// Replaces the pairings saved for `exchange_id`
+fn replace_exchange_pairs(conn: &Connection, exchange_id: i64, pairings: &[EncryptedPairing]) -> Result<()> {
+    reset_pairs_for_exchange(conn, exchange_id)?;
+    for pairing in pairings {
+        let giver_id = ensure_participant(conn, &pairing.giver)?;
+        add_exchange_pair(conn, giver_id, &pairing.receiver, exchange_id)?;
+    }
+    Ok(())
+}
+
+/// Records a draw for `pool` in `year`: the exchange, its participants and
+/// their exclusions, and the pairings, all in one transaction.
+///
+/// Any pairings previously saved for that exchange are replaced.
+pub fn save_draw(
+    conn: &mut Connection,
+    pool: ExchangePool,
+    year: i32,
+    participants: &[Participant],
+    pairings: &[EncryptedPairing],
+) -> Result<i64> {
+    let tx = conn.savepoint()?;
+    let exchange_id = ensure_exchange(&tx, pool, year)?;
+
+    for participant in participants {
+        add_participant(&tx, participant, year)?;
+    }
+
+    replace_exchange_pairs(&tx, exchange_id, pairings)?;
+
+    tx.commit().context("Failed to save the draw")?;
+    Ok(exchange_id)
+}
+
+/// Returns every saved pairing across all years and pools, oldest first
+pub fn all_pairings(conn: &Connection) -> Result<Vec<EncryptedPairing>> {
+    let mut stmt = conn.prepare(
+        "SELECT e.year, e.name, g.name, ep.receiver
+         FROM exchange_pairing ep
+         JOIN exchange e ON e.id = ep.exchange_id
+         JOIN participant g ON g.id = ep.giver_id
+         ORDER BY e.year, e.name, ep.id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i32>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })?;
+
+    let mut pairings = vec![];
+    for row in rows {
+        let (year, pool_name, giver, receiver) = row?;
+        let pool = pool_from_name(&pool_name)
+            .with_context(|| format!("Unknown exchange pool '{pool_name}' in database"))?;
+        pairings.push(EncryptedPairing {
+            year,
+            pool,
+            giver,
+            receiver,
+        });
+    }
+    Ok(pairings)
+}
+
+/// Looks up a pool by the display name stored in `exchange.name`
+fn pool_from_name(name: &str) -> Option<ExchangePool> {
+    ExchangePool::ALL.into_iter().find(|pool| pool.to_string() == name)
+}
+
+fn status_to_sql(status: ExchangeStatus) -> &'static str {
+    match status {
+        ExchangeStatus::Draft => "draft",
+        ExchangeStatus::Finalized => "finalized",
+    }
+}
+
+fn status_from_sql(status: &str) -> Result<ExchangeStatus> {
+    match status {
+        "draft" => Ok(ExchangeStatus::Draft),
+        "finalized" => Ok(ExchangeStatus::Finalized),
+        _ => anyhow::bail!("Unknown exchange status '{status}' in database"),
+    }
+}
+
+fn contribution_status_to_sql(status: ContributionStatus) -> &'static str {
+    match status {
+        ContributionStatus::Committed => "committed",
+        ContributionStatus::Revealed => "revealed",
+        ContributionStatus::Used => "used",
+        ContributionStatus::Forfeited => "forfeited",
+    }
+}
+
+fn contribution_status_from_sql(status: &str) -> Result<ContributionStatus> {
+    match status {
+        "committed" => Ok(ContributionStatus::Committed),
+        "revealed" => Ok(ContributionStatus::Revealed),
+        "used" => Ok(ContributionStatus::Used),
+        "forfeited" => Ok(ContributionStatus::Forfeited),
+        _ => anyhow::bail!("Unknown seed contribution status '{status}' in database"),
+    }
+}
+
+fn sender_to_sql(sender: MessageSender) -> &'static str {
+    match sender {
+        MessageSender::Santa => "santa",
+        MessageSender::Recipient => "recipient",
+    }
+}
+
+fn sender_from_sql(sender: &str) -> Result<MessageSender> {
+    match sender {
+        "santa" => Ok(MessageSender::Santa),
+        "recipient" => Ok(MessageSender::Recipient),
+        _ => anyhow::bail!("Unknown message sender '{sender}' in database"),
+    }
+}
+
+fn reminder_kind_from_sql(kind: &str) -> Result<ReminderKind> {
+    match kind {
+        "deadline" => Ok(ReminderKind::Deadline),
+        "exchange" => Ok(ReminderKind::Exchange),
+        _ => anyhow::bail!("Unknown reminder kind '{kind}' in database"),
+    }
+}
+
+fn priority_from_sql(priority: &str) -> Result<Priority> {
+    Priority::from_slug(priority).with_context(|| format!("Unknown wishlist priority '{priority}' in database"))
+}
+
+fn action_from_sql(action: &str) -> Result<AuditAction> {
+    match action {
+        "draw" => Ok(AuditAction::Draw),
+        "regenerate" => Ok(AuditAction::Regenerate),
+        "finalize" => Ok(AuditAction::Finalize),
+        "unlock" => Ok(AuditAction::Unlock),
+        "reveal" => Ok(AuditAction::Reveal),
+        "break-glass" => Ok(AuditAction::BreakGlass),
+        _ => anyhow::bail!("Unknown audit action '{action}' in database"),
+    }
+}
+
+type RevealTokenRow = (String, i32, String, String, String, String);
+
+/// Reads a `reveal_token` row selected as pool, year, giver, token_hash, encrypted_receiver, encrypted_token
+fn reveal_token_row(row: &rusqlite::Row) -> rusqlite::Result<RevealTokenRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+}
+
+fn reveal_token_from_row(row: RevealTokenRow) -> Result<RevealToken> {
+    let (pool, year, giver, token_hash, encrypted_receiver, encrypted_token) = row;
+    Ok(RevealToken {
+        pool: crate::utils::parse_pool_arg(&pool).map_err(anyhow::Error::msg)?,
+        year,
+        giver,
+        token_hash,
+        encrypted_receiver,
+        encrypted_token,
+    })
+}
+
+/// Storage backed by a SQLite database such as `drawings.db`
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = init_db(path).with_context(|| format!("Failed to open database {}", path.display()))?;
+        Ok(Self { conn })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn in_transaction(&mut self, writes: &mut dyn FnMut(&mut dyn Storage) -> Result<()>) -> Result<()> {
+        // A savepoint rather than a transaction, so the writes' own savepoints nest inside it
+        self.conn.execute_batch("SAVEPOINT in_transaction")?;
+        match writes(self) {
+            Ok(()) => {
+                self.conn.execute_batch("RELEASE in_transaction")?;
+                Ok(())
+            }
+            Err(e) => {
+                self.conn.execute_batch("ROLLBACK TO in_transaction; RELEASE in_transaction")?;
+                Err(e)
+            }
+        }
+    }
+
+    fn save_draw(
+        &mut self,
+        pool: ExchangePool,
+        year: i32,
+        participants: &[Participant],
+        pairings: &[EncryptedPairing],
+    ) -> Result<()> {
+        save_draw(&mut self.conn, pool, year, participants, pairings)?;
+        Ok(())
+    }
+
+    fn replace_pairings(&mut self, pool: ExchangePool, year: i32, pairings: &[EncryptedPairing]) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        let exchange_id = existing_exchange(&tx, pool, year)?;
+        replace_exchange_pairs(&tx, exchange_id, pairings)?;
+        tx.commit().context("Failed to save the pairings")
+    }
+
+    fn load_pairings(&self) -> Result<Vec<EncryptedPairing>> {
+        all_pairings(&self.conn)
+    }
+
+    fn load_themes(&self) -> Result<ThemeHistory> {
+        let mut stmt = self.conn.prepare("SELECT pool, year, value FROM pool_theme")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        let mut history = ThemeHistory::default();
+        for row in rows {
+            let (pool, year, value) = row?;
+            let pool = crate::utils::parse_pool_arg(&pool).map_err(anyhow::Error::msg)?;
+            history.record(pool, year, &value);
+        }
+        Ok(history)
+    }
+
+    fn save_theme(&mut self, pool: ExchangePool, year: i32, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO pool_theme (pool, year, value) VALUES (?1, ?2, ?3)",
+            (pool.slug(), year, value),
+        )?;
+        Ok(())
+    }
+
+    fn exchange_status(&self, pool: ExchangePool, year: i32) -> Result<ExchangeStatus> {
+        let status: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT status FROM exchange WHERE name = ?1 and year = ?2",
+                (&pool.to_string(), &year),
+                |row| row.get(0),
+            )
+            .optional()?;
+        match status {
+            Some(status) => status_from_sql(&status),
+            None => Ok(ExchangeStatus::Draft),
+        }
+    }
+
+    fn set_exchange_status(&mut self, pool: ExchangePool, year: i32, status: ExchangeStatus) -> Result<()> {
+        let exchange_id = existing_exchange(&self.conn, pool, year)?;
+        self.conn.execute(
+            "UPDATE exchange SET status = ?1 WHERE id = ?2",
+            (status_to_sql(status), exchange_id),
+        )?;
+        Ok(())
+    }
+
+    fn record_unlock(&mut self, pool: ExchangePool, year: i32, unlock: &Unlock) -> Result<()> {
+        let exchange_id = existing_exchange(&self.conn, pool, year)?;
+        self.conn.execute(
+            "INSERT INTO exchange_unlock (exchange_id, unlocked_by, unlocked_at, reason) VALUES (?1, ?2, ?3, ?4)",
+            (exchange_id, &unlock.unlocked_by, &unlock.unlocked_at, &unlock.reason),
+        )?;
+        Ok(())
+    }
+
+    fn load_unlocks(&self, pool: ExchangePool, year: i32) -> Result<Vec<Unlock>> {
+        let Some(exchange_id) = find_exchange(&self.conn, pool, year)? else {
+            return Ok(vec![]);
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT unlocked_by, unlocked_at, reason FROM exchange_unlock WHERE exchange_id = ?1 ORDER BY id",
+        )?;
+        let unlocks = stmt
+            .query_map([exchange_id], |row| {
+                Ok(Unlock {
+                    unlocked_by: row.get(0)?,
+                    unlocked_at: row.get(1)?,
+                    reason: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(unlocks)
+    }
+
+    fn append_audit(&mut self, entry: &AuditEntry) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO audit_log (timestamp, actor, action, pool, year, seed, result_hash, commitment, detail)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            (
+                &entry.timestamp,
+                &entry.actor,
+                entry.action.to_string(),
+                entry.pool.slug(),
+                entry.year,
+                &entry.seed,
+                &entry.result_hash,
+                &entry.commitment,
+                &entry.detail,
+            ),
+        )?;
+        Ok(())
+    }
+
+    fn load_audit(&self) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, actor, action, pool, year, seed, result_hash, commitment, detail FROM audit_log ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i32>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+            ))
+        })?;
+
+        let mut entries = vec![];
+        for row in rows {
+            let (timestamp, actor, action, pool, year, seed, result_hash, commitment, detail) = row?;
+            entries.push(AuditEntry {
+                timestamp,
+                actor,
+                action: action_from_sql(&action)?,
+                pool: crate::utils::parse_pool_arg(&pool).map_err(anyhow::Error::msg)?,
+                year,
+                seed,
+                result_hash,
+                commitment,
+                detail,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn save_reveal_tokens(&mut self, pool: ExchangePool, year: i32, tokens: &[RevealToken]) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        tx.execute(
+            "DELETE FROM reveal_token WHERE pool = ?1 AND year = ?2",
+            (pool.slug(), year),
+        )?;
+        for token in tokens {
+            tx.execute(
+                "INSERT INTO reveal_token (pool, year, giver, token_hash, encrypted_receiver, encrypted_token)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                (
+                    token.pool.slug(),
+                    token.year,
+                    &token.giver,
+                    &token.token_hash,
+                    &token.encrypted_receiver,
+                    &token.encrypted_token,
+                ),
+            )?;
+        }
+        tx.commit().context("Failed to save reveal tokens")
+    }
+
+    fn save_commitment(&mut self, commitment: &DrawCommitment) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO draw_commitment (pool, year, commitment, encrypted_opening) VALUES (?1, ?2, ?3, ?4)",
+            (
+                commitment.pool.slug(),
+                commitment.year,
+                &commitment.commitment,
+                &commitment.encrypted_opening,
+            ),
+        )?;
+        Ok(())
+    }
+
+    fn load_commitment(&self, pool: ExchangePool, year: i32) -> Result<Option<DrawCommitment>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT commitment, encrypted_opening FROM draw_commitment WHERE pool = ?1 AND year = ?2",
+                (pool.slug(), year),
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        Ok(row.map(|(commitment, encrypted_opening)| DrawCommitment {
+            pool,
+            year,
+            commitment,
+            encrypted_opening,
+        }))
+    }
+
+    fn save_seed_contributions(
+        &mut self,
+        pool: ExchangePool,
+        year: i32,
+        contributions: &[SeedContribution],
+    ) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        tx.execute(
+            "DELETE FROM seed_contribution WHERE pool = ?1 AND year = ?2",
+            (pool.slug(), year),
+        )?;
+        for contribution in contributions {
+            tx.execute(
+                "INSERT INTO seed_contribution
+                 (pool, year, participant, commitment, value, status, committed_at, revealed_at, reveal_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                (
+                    pool.slug(),
+                    year,
+                    &contribution.participant,
+                    &contribution.commitment,
+                    &contribution.value,
+                    contribution_status_to_sql(contribution.status),
+                    &contribution.committed_at,
+                    &contribution.revealed_at,
+                    &contribution.reveal_by,
+                ),
+            )?;
+        }
+        tx.commit().context("Failed to save seed contributions")
+    }
+
+    fn load_seed_contributions(&self, pool: ExchangePool, year: i32) -> Result<Vec<SeedContribution>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT participant, commitment, value, status, committed_at, revealed_at, reveal_by
+             FROM seed_contribution WHERE pool = ?1 AND year = ?2 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map((pool.slug(), year), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(participant, commitment, value, status, committed_at, revealed_at, reveal_by)| {
+                Ok(SeedContribution {
+                    pool,
+                    year,
+                    participant,
+                    commitment,
+                    value,
+                    status: contribution_status_from_sql(&status)?,
+                    committed_at,
+                    revealed_at,
+                    reveal_by,
+                })
+            })
+            .collect()
+    }
+
+    fn append_message(&mut self, message: &Message) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO message (pool, year, recipient, sender, body, sent_on) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                message.pool.slug(),
+                message.year,
+                &message.recipient,
+                sender_to_sql(message.sender),
+                &message.body,
+                &message.sent_on,
+            ),
+        )?;
+        Ok(())
+    }
+
+    fn load_messages(&self, pool: ExchangePool, year: i32) -> Result<Vec<Message>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT recipient, sender, body, sent_on FROM message WHERE pool = ?1 AND year = ?2 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map((pool.slug(), year), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(recipient, sender, body, sent_on)| {
+                Ok(Message {
+                    pool,
+                    year,
+                    recipient,
+                    sender: sender_from_sql(&sender)?,
+                    body,
+                    sent_on,
+                })
+            })
+            .collect()
+    }
+
+    fn clear_messages(&mut self, pool: ExchangePool, year: i32) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM message WHERE pool = ?1 AND year = ?2", (pool.slug(), year))?;
+        Ok(())
+    }
+
+    fn record_reminder(&mut self, reminder: &SentReminder) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO sent_reminder (pool, year, kind, days_before, sent_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                reminder.pool.slug(),
+                reminder.year,
+                reminder.kind.slug(),
+                reminder.days_before,
+                &reminder.sent_at,
+            ),
+        )?;
+        Ok(inserted == 1)
+    }
+
+    fn forget_reminder(&mut self, reminder: &SentReminder) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM sent_reminder WHERE pool = ?1 AND year = ?2 AND kind = ?3 AND days_before = ?4",
+            (reminder.pool.slug(), reminder.year, reminder.kind.slug(), reminder.days_before),
+        )?;
+        Ok(())
+    }
+
+    fn load_sent_reminders(&self, pool: ExchangePool, year: i32) -> Result<Vec<SentReminder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, days_before, sent_at FROM sent_reminder WHERE pool = ?1 AND year = ?2 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map((pool.slug(), year), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(kind, days_before, sent_at)| {
+                Ok(SentReminder {
+                    pool,
+                    year,
+                    kind: reminder_kind_from_sql(&kind)?,
+                    days_before,
+                    sent_at,
+                })
+            })
+            .collect()
+    }
+
+    fn save_wishlist(&mut self, owner: &str, items: &[WishlistItem]) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        tx.execute("DELETE FROM wishlist_item WHERE owner = ?1", [owner])?;
+        for item in items {
+            tx.execute(
+                "INSERT INTO wishlist_item (owner, idea, link, price, priority, added_on) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                (owner, &item.idea, &item.link, &item.price, item.priority.slug(), &item.added_on),
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_wishlist(&self, owner: &str) -> Result<Vec<WishlistItem>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT idea, link, price, priority, added_on FROM wishlist_item WHERE owner = ?1 ORDER BY id")?;
+        let rows = stmt
+            .query_map([owner], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(idea, link, price, priority, added_on)| {
+                Ok(WishlistItem {
+                    idea,
+                    link,
+                    price,
+                    priority: priority_from_sql(&priority)?,
+                    added_on,
+                })
+            })
+            .collect()
+    }
+
+    fn load_reveal_tokens(&self, pool: ExchangePool, year: i32) -> Result<Vec<RevealToken>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT pool, year, giver, token_hash, encrypted_receiver, encrypted_token
+             FROM reveal_token WHERE pool = ?1 AND year = ?2 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map((pool.slug(), year), reveal_token_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter().map(reveal_token_from_row).collect()
+    }
+
+    fn find_reveal_token(&self, token_hash: &str) -> Result<Option<RevealToken>> {
+        self.conn
+            .query_row(
+                "SELECT pool, year, giver, token_hash, encrypted_receiver, encrypted_token
+                 FROM reveal_token WHERE token_hash = ?1",
+                [token_hash],
+                reveal_token_row,
+            )
+            .optional()?
+            .map(reveal_token_from_row)
+            .transpose()
+    }
+}
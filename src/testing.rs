@@ -0,0 +1,44 @@
+//! Fixtures shared by the unit tests
+
+use std::path::PathBuf;
+
+use crate::{
+    exchange::{ExclusionReason, Participant},
+    giftexchange::ExchangePool,
+    storage::JsonStorage,
+};
+
+/// A JSON storage path in the temp directory, removed along with its lock file when dropped
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    pub fn new(name: &str) -> Self {
+        TempPath(std::env::temp_dir().join(format!("christmas-{}-{name}.json", std::process::id())))
+    }
+
+    /// JSON storage in this file
+    pub fn storage(&self) -> JsonStorage {
+        JsonStorage::open(&self.0).unwrap()
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+        let _ = std::fs::remove_file(self.0.with_extension("json.lock"));
+    }
+}
+
+/// A small Island Life roster: Alec and Kaylee are married, Will, Grace and Noel are single
+pub fn roster() -> Vec<Participant> {
+    let person = |name: &str, excludes: Vec<(&str, ExclusionReason)>| {
+        Participant::new(name.to_string(), vec![ExchangePool::IslandLife], excludes)
+    };
+    vec![
+        person("Alec", vec![("Kaylee", ExclusionReason::Spouse)]),
+        person("Kaylee", vec![("Alec", ExclusionReason::Spouse)]),
+        person("Will", vec![]),
+        person("Grace", vec![]),
+        person("Noel", vec![]),
+    ]
+}
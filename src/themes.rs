@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use crate::giftexchange::ExchangePool;
+
+/// Letters eligible for the Grabergishimazureson letter of the year
+pub const GRABER_LETTERS: &str = "ACDIJLMNORSTUXYZ";
+
+/// How many previous years' themes are avoided when drawing a new theme
+pub const DEFAULT_REPEAT_WINDOW: i32 = 5;
+
+/// What kind of constraint a pool's theme puts on gifts
+// Not every kind is configured in `data::theme_config_for_pool` at any one time
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    /// Gifts start with the given letter
+    Letter,
+    /// Gifts are in the given colour
+    Colour,
+    /// Free-form idea such as "handmade only" or "a book and a snack"
+    Idea,
+}
+
+impl ThemeKind {
+    /// Heading shown above the theme, e.g. "Letter for 2024"
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThemeKind::Letter => "Letter",
+            ThemeKind::Colour => "Colour",
+            ThemeKind::Idea => "Theme",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeOption {
+    pub value: String,
+    pub weight: u32,
+}
+
+/// The themes a pool can draw from and how repeats are avoided
+#[derive(Debug, Clone)]
+pub struct ThemeConfig {
+    pub kind: ThemeKind,
+    pub options: Vec<ThemeOption>,
+    /// Themes used within this many previous years are avoided
+    pub repeat_window: i32,
+    /// Use every option once before any option repeats
+    pub exhaust_before_repeat: bool,
+}
+
+impl ThemeConfig {
+    /// A theme that never changes, like Island Life's 'I'
+    pub fn fixed(kind: ThemeKind, value: &str) -> Self {
+        Self::weighted(kind, &[(value, 1)])
+    }
+
+    /// One letter per year, cycling through every letter in `letters` before repeating
+    pub fn letters(letters: &str) -> Self {
+        let options = letters
+            .chars()
+            .map(|c| ThemeOption {
+                value: c.to_string(),
+                weight: 1,
+            })
+            .collect();
+        ThemeConfig {
+            kind: ThemeKind::Letter,
+            options,
+            repeat_window: DEFAULT_REPEAT_WINDOW,
+            exhaust_before_repeat: true,
+        }
+    }
+
+    /// Options picked in proportion to their weight, avoiding recent repeats
+    pub fn weighted(kind: ThemeKind, options: &[(&str, u32)]) -> Self {
+        let options = options
+            .iter()
+            .map(|(value, weight)| ThemeOption {
+                value: value.to_string(),
+                weight: *weight,
+            })
+            .collect();
+        ThemeConfig {
+            kind,
+            options,
+            repeat_window: DEFAULT_REPEAT_WINDOW,
+            exhaust_before_repeat: false,
+        }
+    }
+}
+
+/// The theme drawn for a pool in a given year
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GiftTheme {
+    pub kind: ThemeKind,
+    pub value: String,
+}
+
+impl Display for GiftTheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeRecord {
+    pub pool: ExchangePool,
+    pub year: i32,
+    pub value: String,
+}
+
+/// Themes previously drawn for each pool, one per pool per year
+#[derive(Debug, Default, Clone)]
+pub struct ThemeHistory {
+    records: Vec<ThemeRecord>,
+}
+
+impl ThemeHistory {
+    /// Returns the theme already drawn for `pool` in `year`, if any
+    pub fn theme_for(&self, pool: ExchangePool, year: i32) -> Option<&str> {
+        self.records
+            .iter()
+            .find(|r| r.pool == pool && r.year == year)
+            .map(|r| r.value.as_str())
+    }
+
+    /// Records the theme for `pool` in `year`, replacing any earlier entry
+    pub fn record(&mut self, pool: ExchangePool, year: i32, value: &str) {
+        self.records.retain(|r| !(r.pool == pool && r.year == year));
+        self.records.push(ThemeRecord {
+            pool,
+            year,
+            value: value.to_string(),
+        });
+        self.records.sort_by_key(|r| (r.pool.slug(), r.year));
+    }
+
+    /// Themes drawn for `pool` before `year`, oldest first
+    fn themes_before(&self, pool: ExchangePool, year: i32) -> Vec<(i32, &str)> {
+        self.records
+            .iter()
+            .filter(|r| r.pool == pool && r.year < year)
+            .map(|r| (r.year, r.value.as_str()))
+            .collect()
+    }
+}
+
+/// Draws the theme for `pool` in `year` according to `config`.
+///
+/// Options used within the last `repeat_window` years are avoided and, when
+/// `exhaust_before_repeat` is set, every option is used once before any option
+/// repeats. Remaining options are picked in proportion to their weight. The
+/// draw is seeded from the pool and year, so the same history always yields the
+/// same theme.
+pub fn draw_theme(pool: ExchangePool, year: i32, config: &ThemeConfig, history: &ThemeHistory) -> GiftTheme {
+    let past = history.themes_before(pool, year);
+    let values: Vec<&str> = config.options.iter().map(|o| o.value.as_str()).collect();
+
+    // Walk the history oldest first, starting a new cycle whenever every option has been used
+    let mut used_this_cycle = HashSet::new();
+    if config.exhaust_before_repeat {
+        for (_, value) in &past {
+            used_this_cycle.insert(*value);
+            if values.iter().all(|v| used_this_cycle.contains(v)) {
+                used_this_cycle.clear();
+            }
+        }
+    }
+
+    let recent: HashSet<&str> = past
+        .iter()
+        .filter(|(y, _)| year - y <= config.repeat_window)
+        .map(|(_, value)| *value)
+        .collect();
+
+    let eligible = |option: &&ThemeOption, avoid_recent: bool| {
+        option.weight > 0
+            && !used_this_cycle.contains(option.value.as_str())
+            && !(avoid_recent && recent.contains(option.value.as_str()))
+    };
+    let mut candidates: Vec<&ThemeOption> = config.options.iter().filter(|o| eligible(o, true)).collect();
+    if candidates.is_empty() {
+        candidates = config.options.iter().filter(|o| eligible(o, false)).collect();
+    }
+    if candidates.is_empty() {
+        candidates = config.options.iter().collect();
+    }
+
+    let mut rng = fastrand::Rng::with_seed(theme_seed(pool, year));
+    let total: u32 = candidates.iter().map(|o| o.weight.max(1)).sum();
+    let mut pick = rng.u32(0..total.max(1));
+    let chosen = candidates
+        .iter()
+        .find(|o| {
+            let weight = o.weight.max(1);
+            if pick < weight {
+                true
+            } else {
+                pick -= weight;
+                false
+            }
+        })
+        .expect("theme config must have at least one option");
+
+    GiftTheme {
+        kind: config.kind,
+        value: chosen.value.clone(),
+    }
+}
+
+fn theme_seed(pool: ExchangePool, year: i32) -> u64 {
+    pool.slug()
+        .bytes()
+        .fold(year as u64, |seed, b| seed.wrapping_mul(31).wrapping_add(b as u64))
+}
@@ -1,52 +1,676 @@
 use crate::{
-    data,
-    exchange::ParticipantGraph,
     giftexchange::ExchangePool,
-    utils::{current_year, letter_for_pool},
+    lifecycle::ExchangeStatus,
+    messages::{Message, MessageBoard, MessageSender},
+    server::{self, ExchangePairing, ExchangeResult, RevealLink},
+    stats::PairStats,
+    themes::ThemeKind,
+    wishlist::{NewWish, Priority, WishlistBoard, WishlistItem},
 };
 use dioxus::prelude::*;
 use dioxus::document::Title;
 
-#[derive(Clone, Debug)]
-pub struct ExchangePairing {
-    pub giver: String,
-    pub receiver: String,
+#[derive(Routable, Clone, PartialEq)]
+enum Route {
+    /// Drawing, finalizing and handing out reveal links
+    #[route("/")]
+    Organizer {},
+    /// One giver's own receiver, opened from their reveal link
+    #[route("/reveal/:token")]
+    Reveal { token: String },
+    /// Slips for every giver to print, cut out and fold
+    #[route("/slips/:pool")]
+    Slips { pool: String },
 }
 
-#[derive(Clone, Debug)]
-pub struct ExchangeResult {
-    pub pairings: Vec<ExchangePairing>,
-    pub year_letter: char,
-    pub year: i32,
+pub fn app() -> Element {
+    rsx! {
+        Router::<Route> {}
+    }
 }
 
-pub fn generate_exchange_pairings(pool: ExchangePool) -> ExchangeResult {
-    let participants = data::get_participants_by_pool(pool);
-    let graph = ParticipantGraph::from_participants(participants);
-    let exchange = graph.build_exchange();
+/// Matrix of how often each giver (rows) has drawn each receiver (columns)
+#[component]
+fn PairHeatmap(stats: PairStats) -> Element {
+    if stats.people.is_empty() {
+        return rsx! {
+            p {
+                style: "color: #6b7280;",
+                "No saved draws yet."
+            }
+        };
+    }
 
-    let pairings = exchange
-        .into_iter()
-        .map(|(giver, receiver)| ExchangePairing { giver, receiver })
-        .collect();
+    let max_count = stats.max_count().max(1);
+    rsx! {
+        p {
+            style: "color: #6b7280; margin-bottom: 0.75rem;",
+            "Across {stats.years.len()} saved years. Rows give to columns."
+        }
+        div {
+            style: "overflow-x: auto;",
+            table {
+                style: "border-collapse: collapse; font-size: 0.875rem;",
+                tr {
+                    th {}
+                    for receiver in stats.people.iter() {
+                        th {
+                            style: "padding: 0.25rem 0.5rem; color: #374151; font-weight: 500;",
+                            "{receiver}"
+                        }
+                    }
+                }
+                for giver in stats.people.iter() {
+                    tr {
+                        th {
+                            style: "padding: 0.25rem 0.5rem; color: #374151; font-weight: 500; text-align: right;",
+                            "{giver}"
+                        }
+                        for receiver in stats.people.iter() {
+                            if giver == receiver {
+                                td {
+                                    style: "background: #e5e7eb; border: 1px solid white; min-width: 2rem;",
+                                }
+                            } else {
+                                td {
+                                    style: "background: rgba(22, 163, 74, {stats.count(giver, receiver) as f64 / max_count as f64}); border: 1px solid white; text-align: center; padding: 0.25rem; min-width: 2rem; color: #1f2937;",
+                                    "{stats.count(giver, receiver)}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
 
-    let year = current_year();
-    let year_letter = letter_for_pool(pool);
+/// A giver's reveal page, which shows only who they give to
+#[component]
+fn Reveal(token: String) -> Element {
+    let board_token = token.clone();
+    let reveal = use_resource(move || {
+        let token = token.clone();
+        async move { server::reveal_recipient(token).await }
+    });
 
-    ExchangeResult {
-        pairings,
-        year_letter,
-        year,
+    rsx! {
+        Title {"Your Gift Exchange Draw"}
+        div {
+            style: "min-height: 100vh; background: linear-gradient(to bottom right, #fee2e2, #dcfce7); padding: 2rem;",
+            div {
+                style: "max-width: 32rem; margin: 0 auto; background: white; border-radius: 0.5rem; box-shadow: 0 10px 15px -3px rgba(0, 0, 0, 0.1); padding: 2rem; text-align: center;",
+                match &*reveal.read() {
+                    Some(Ok(reveal)) => rsx! {
+                        p {
+                            style: "font-size: 1.125rem; color: #374151;",
+                            "{reveal.giver}, for {reveal.pool} {reveal.year} you're giving to"
+                        }
+                        h1 {
+                            style: "font-size: 2.5rem; font-weight: bold; color: #166534; margin: 1rem 0;",
+                            "🎁 {reveal.receiver}"
+                        }
+                        p {
+                            style: "font-size: 1.125rem; color: #374151;",
+                            "{reveal.theme.kind.label()}: {reveal.theme}"
+                        }
+                        if let Some(budget) = &reveal.budget {
+                            p {
+                                style: "font-size: 1.125rem; color: #374151;",
+                                "Budget: {budget}"
+                            }
+                        }
+                        p {
+                            style: "margin-top: 1.5rem; color: #6b7280;",
+                            "Keep it secret! 🤫"
+                        }
+                        Wishlists { token: board_token.clone() }
+                        Messages { token: board_token.clone() }
+                    },
+                    Some(Err(e)) => rsx! {
+                        p {
+                            style: "color: #991b1b;",
+                            "{e}"
+                        }
+                    },
+                    None => rsx! {
+                        p { "Loading…" }
+                    },
+                }
+            }
+        }
     }
 }
 
-pub fn app() -> Element {
+/// A printable page of slips, one per giver, for the pool with slug `pool`
+#[component]
+fn Slips(pool: String) -> Element {
+    let Some(pool) = ExchangePool::from_slug(&pool) else {
+        return rsx! {
+            p {
+                style: "color: #991b1b; padding: 2rem;",
+                "There's no '{pool}' exchange."
+            }
+        };
+    };
+    let slips = use_resource(move || async move { server::printable_slips(pool).await });
+
+    rsx! {
+        Title {"{pool} Gift Slips"}
+        div {
+            style: "padding: 1rem;",
+            div {
+                class: "no-print",
+                style: "display: flex; align-items: center; gap: 1rem; margin-bottom: 1rem;",
+                button {
+                    style: "padding: 0.5rem 1rem; background: #16a34a; color: white; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;",
+                    onclick: move |_| { document::eval("window.print()"); },
+                    "🖨️ Print"
+                }
+                span {
+                    style: "color: #6b7280;",
+                    "Cut along the dashed lines and fold along the dotted ones. Choose \"Save as PDF\" when printing for a PDF."
+                }
+            }
+            match &*slips.read() {
+                Some(Ok(html)) => rsx! {
+                    div { dangerous_inner_html: "{html}" }
+                },
+                Some(Err(e)) => rsx! {
+                    p {
+                        style: "color: #991b1b;",
+                        "{e}"
+                    }
+                },
+                None => rsx! {
+                    p { "Loading…" }
+                },
+            }
+        }
+    }
+}
+
+/// The recipient's wishlist, and the giver's own to edit for their Santa
+#[component]
+fn Wishlists(token: String) -> Element {
+    let mut board = use_signal(|| None::<WishlistBoard>);
+    let mut error = use_signal(|| None::<String>);
+    let mut by_letter = use_signal(|| false);
+    let mut idea = use_signal(String::new);
+    let mut link = use_signal(String::new);
+    let mut price = use_signal(String::new);
+    let mut priority = use_signal(|| Priority::Medium);
+
+    // Shows the lists after loading or changing them, returning whether it worked
+    let mut show = move |result: Result<WishlistBoard, ServerFnError>| match result {
+        Ok(result) => {
+            board.set(Some(result));
+            error.set(None);
+            true
+        }
+        Err(e) => {
+            error.set(Some(e.to_string()));
+            false
+        }
+    };
+
+    let load_token = token.clone();
+    use_hook(move || spawn(async move { show(server::load_wishlists(load_token).await); }));
+
+    let add_token = token.clone();
+    let add = move |_| {
+        let token = add_token.clone();
+        async move {
+            let wish = NewWish {
+                idea: idea(),
+                link: link(),
+                price: price(),
+                priority: priority(),
+            };
+            if show(server::add_wish(token, wish).await) {
+                idea.set(String::new());
+                link.set(String::new());
+                price.set(String::new());
+                priority.set(Priority::Medium);
+            }
+        }
+    };
+
+    let Some(current) = board() else {
+        return rsx! {
+            if let Some(error) = error() {
+                p {
+                    style: "margin-top: 1.5rem; color: #991b1b;",
+                    "{error}"
+                }
+            }
+        };
+    };
+    let ideas: Vec<WishlistItem> = current.recipient_ideas(by_letter()).into_iter().cloned().collect();
+    let input_style = "width: 100%; padding: 0.5rem; border: 1px solid #d1d5db; border-radius: 0.375rem; box-sizing: border-box; margin-top: 0.5rem;";
+    rsx! {
+        div {
+            style: "margin-top: 2rem; text-align: left;",
+            h2 {
+                style: "font-size: 1.25rem; font-weight: 600; color: #1f2937;",
+                "🎁 {current.recipient}'s wishlist"
+            }
+            if let Some(letter) = current.letter() {
+                label {
+                    style: "display: block; color: #374151; font-size: 0.875rem; margin: 0.5rem 0;",
+                    input {
+                        r#type: "checkbox",
+                        checked: by_letter(),
+                        onchange: move |e| by_letter.set(e.checked()),
+                    }
+                    " Only ideas starting with {letter}"
+                }
+            }
+            if current.recipient_items.is_empty() {
+                p {
+                    style: "color: #9ca3af; font-style: italic; margin: 0.5rem 0;",
+                    "No ideas yet. Ask {current.recipient} below!"
+                }
+            } else if ideas.is_empty() {
+                p {
+                    style: "color: #9ca3af; font-style: italic; margin: 0.5rem 0;",
+                    "None of their ideas fit this year's letter."
+                }
+            }
+            for item in ideas {
+                WishlistEntry { item }
+            }
+
+            h2 {
+                style: "font-size: 1.25rem; font-weight: 600; color: #1f2937; margin-top: 1.5rem;",
+                "📝 Your wishlist"
+            }
+            p {
+                style: "color: #6b7280; font-size: 0.875rem;",
+                "Only your Santa sees this."
+            }
+            for (index, item) in current.my_items.iter().cloned().enumerate() {
+                div {
+                    style: "display: flex; align-items: center; gap: 0.5rem;",
+                    div {
+                        style: "flex: 1;",
+                        WishlistEntry { item }
+                    }
+                    button {
+                        style: "padding: 0.25rem 0.5rem; background: #e5e7eb; color: #374151; border-radius: 0.375rem; border: none; cursor: pointer;",
+                        onclick: {
+                            let token = token.clone();
+                            move |_| {
+                                let token = token.clone();
+                                async move { show(server::remove_wish(token, index).await); }
+                            }
+                        },
+                        "Remove"
+                    }
+                }
+            }
+            input {
+                style: input_style,
+                placeholder: "A gift idea",
+                value: "{idea}",
+                oninput: move |e| idea.set(e.value()),
+            }
+            input {
+                style: input_style,
+                placeholder: "Link (optional)",
+                value: "{link}",
+                oninput: move |e| link.set(e.value()),
+            }
+            div {
+                style: "display: flex; gap: 0.5rem;",
+                input {
+                    style: input_style,
+                    placeholder: "Rough price (optional)",
+                    value: "{price}",
+                    oninput: move |e| price.set(e.value()),
+                }
+                select {
+                    style: input_style,
+                    value: priority().slug(),
+                    onchange: move |e| priority.set(Priority::from_slug(&e.value()).unwrap_or(Priority::Medium)),
+                    option { value: "high", "Really want it" }
+                    option { value: "medium", "Would like it" }
+                    option { value: "low", "Nice to have" }
+                }
+            }
+            button {
+                style: "margin-top: 0.5rem; padding: 0.5rem 1rem; background: #16a34a; color: white; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;",
+                onclick: add,
+                "Add to my wishlist"
+            }
+
+            if let Some(error) = error() {
+                p {
+                    style: "margin-top: 1rem; color: #991b1b;",
+                    "{error}"
+                }
+            }
+        }
+    }
+}
+
+/// One wishlist idea, with its link, price estimate and priority
+#[component]
+fn WishlistEntry(item: WishlistItem) -> Element {
+    let (badge, colour) = match item.priority {
+        Priority::High => ("Really wants it", "#dc2626"),
+        Priority::Medium => ("Would like it", "#16a34a"),
+        Priority::Low => ("Nice to have", "#6b7280"),
+    };
+    rsx! {
+        div {
+            style: "margin: 0.5rem 0; padding: 0.5rem 0.75rem; background: #f9fafb; border-radius: 0.375rem;",
+            p {
+                style: "margin: 0; color: #1f2937;",
+                if let Some(link) = &item.link {
+                    a {
+                        style: "color: #16a34a;",
+                        href: "{link}",
+                        target: "_blank",
+                        rel: "noopener noreferrer",
+                        "{item.idea}"
+                    }
+                } else {
+                    "{item.idea}"
+                }
+            }
+            p {
+                style: "font-size: 0.75rem; color: #6b7280; margin: 0.25rem 0 0;",
+                span {
+                    style: "color: {colour}; font-weight: 500;",
+                    "{badge}"
+                }
+                if let Some(price) = &item.price {
+                    " · about {price}"
+                }
+            }
+        }
+    }
+}
+
+/// Anonymous threads with the person a giver gives to and with their own Santa
+#[component]
+fn Messages(token: String) -> Element {
+    let mut board = use_signal(|| None::<MessageBoard>);
+    let mut error = use_signal(|| None::<String>);
+    let mut to_recipient = use_signal(String::new);
+    let mut to_santa = use_signal(String::new);
+
+    // Shows the board after loading or sending, returning whether it worked
+    let mut show = move |result: Result<MessageBoard, ServerFnError>| match result {
+        Ok(result) => {
+            board.set(Some(result));
+            error.set(None);
+            true
+        }
+        Err(e) => {
+            error.set(Some(e.to_string()));
+            false
+        }
+    };
+
+    let load_token = token.clone();
+    use_hook(move || spawn(async move { show(server::load_messages(load_token).await); }));
+
+    let recipient_token = token.clone();
+    let send_to_recipient = move |_| {
+        let token = recipient_token.clone();
+        async move {
+            if show(server::message_recipient(token, to_recipient()).await) {
+                to_recipient.set(String::new());
+            }
+        }
+    };
+    let send_to_santa = move |_| {
+        let token = token.clone();
+        async move {
+            if show(server::message_santa(token, to_santa()).await) {
+                to_santa.set(String::new());
+            }
+        }
+    };
+
+    let Some(current) = board() else {
+        return rsx! {
+            if let Some(error) = error() {
+                p {
+                    style: "margin-top: 1.5rem; color: #991b1b;",
+                    "{error}"
+                }
+            }
+        };
+    };
+    rsx! {
+        div {
+            style: "margin-top: 2rem; text-align: left;",
+            h2 {
+                style: "font-size: 1.25rem; font-weight: 600; color: #1f2937;",
+                "💬 {current.recipient}"
+            }
+            p {
+                style: "color: #6b7280; font-size: 0.875rem;",
+                "They'll see your messages from \"Your Santa\", never your name."
+            }
+            Thread { messages: current.my_recipient.clone(), mine: MessageSender::Santa, other: current.recipient.clone() }
+            textarea {
+                style: "width: 100%; min-height: 4rem; padding: 0.5rem; border: 1px solid #d1d5db; border-radius: 0.375rem; box-sizing: border-box;",
+                placeholder: "Ask {current.recipient} something…",
+                value: "{to_recipient}",
+                oninput: move |e| to_recipient.set(e.value()),
+            }
+            button {
+                style: "margin-top: 0.5rem; padding: 0.5rem 1rem; background: #16a34a; color: white; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;",
+                onclick: send_to_recipient,
+                "Send to {current.recipient}"
+            }
+
+            h2 {
+                style: "font-size: 1.25rem; font-weight: 600; color: #1f2937; margin-top: 1.5rem;",
+                "🎅 Your Santa"
+            }
+            p {
+                style: "color: #6b7280; font-size: 0.875rem;",
+                "Whoever is giving to you."
+            }
+            Thread { messages: current.my_santa.clone(), mine: MessageSender::Recipient, other: "Your Santa".to_string() }
+            textarea {
+                style: "width: 100%; min-height: 4rem; padding: 0.5rem; border: 1px solid #d1d5db; border-radius: 0.375rem; box-sizing: border-box;",
+                placeholder: "Reply to your Santa…",
+                value: "{to_santa}",
+                oninput: move |e| to_santa.set(e.value()),
+            }
+            button {
+                style: "margin-top: 0.5rem; padding: 0.5rem 1rem; background: #dc2626; color: white; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;",
+                onclick: send_to_santa,
+                "Send to your Santa"
+            }
+
+            if let Some(error) = error() {
+                p {
+                    style: "margin-top: 1rem; color: #991b1b;",
+                    "{error}"
+                }
+            }
+        }
+    }
+}
+
+/// One thread of messages, with `mine` shown as "You" and the other side as `other`
+#[component]
+fn Thread(messages: Vec<Message>, mine: MessageSender, other: String) -> Element {
+    if messages.is_empty() {
+        return rsx! {
+            p {
+                style: "color: #9ca3af; font-style: italic; margin: 0.5rem 0;",
+                "No messages yet."
+            }
+        };
+    }
+
+    rsx! {
+        for message in messages.iter() {
+            div {
+                style: if message.sender == mine {
+                    "margin: 0.5rem 0 0.5rem 2rem; padding: 0.5rem 0.75rem; background: #dcfce7; border-radius: 0.375rem;"
+                } else {
+                    "margin: 0.5rem 2rem 0.5rem 0; padding: 0.5rem 0.75rem; background: #f3f4f6; border-radius: 0.375rem;"
+                },
+                p {
+                    style: "font-size: 0.75rem; color: #6b7280; margin: 0;",
+                    if message.sender == mine { "You" } else { "{other}" }
+                    " · {message.sent_on}"
+                }
+                p {
+                    style: "margin: 0.25rem 0 0; white-space: pre-wrap; color: #1f2937;",
+                    "{message.body}"
+                }
+            }
+        }
+    }
+}
+
+/// The organizer's page, shown once they've signed in with their sign-in key
+#[component]
+fn Organizer() -> Element {
+    // None until the server says whether anyone is signed in on this browser
+    let mut signed_in = use_signal(|| None::<Option<String>>);
+    let mut key = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+
+    use_hook(move || {
+        spawn(async move {
+            match server::current_organizer().await {
+                Ok(organizer) => signed_in.set(Some(organizer)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        })
+    });
+
+    let sign_in = move |e: FormEvent| async move {
+        e.prevent_default();
+        match server::sign_in(key()).await {
+            Ok(organizer) => {
+                key.set(String::new());
+                error.set(None);
+                signed_in.set(Some(Some(organizer)));
+            }
+            Err(e) => error.set(Some(e.to_string())),
+        }
+    };
+    let sign_out = move |_| async move {
+        match server::sign_out().await {
+            Ok(()) => signed_in.set(Some(None)),
+            Err(e) => error.set(Some(e.to_string())),
+        }
+    };
+
+    if let Some(Some(organizer)) = signed_in() {
+        return rsx! { Dashboard { organizer, on_sign_out: sign_out } };
+    }
+
+    rsx! {
+        Title {"Christmas Gift Exchange"}
+        div {
+            style: "min-height: 100vh; background: linear-gradient(to bottom right, #fee2e2, #dcfce7); padding: 2rem;",
+            div {
+                style: "max-width: 32rem; margin: 0 auto; background: white; border-radius: 0.5rem; box-shadow: 0 10px 15px -3px rgba(0, 0, 0, 0.1); padding: 2rem;",
+                h1 {
+                    style: "font-size: 2rem; font-weight: bold; text-align: center; margin-bottom: 1.5rem; color: #166534;",
+                    "🎄 Organizer Sign In"
+                }
+                if signed_in().is_some() {
+                    form {
+                        style: "display: flex; flex-direction: column; gap: 0.75rem;",
+                        onsubmit: sign_in,
+                        input {
+                            style: "padding: 0.5rem; border: 1px solid #d1d5db; border-radius: 0.375rem;",
+                            r#type: "password",
+                            placeholder: "Your organizer sign-in key",
+                            value: "{key}",
+                            oninput: move |e| key.set(e.value()),
+                        }
+                        button {
+                            style: "padding: 0.5rem 1rem; background: #16a34a; color: white; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;",
+                            r#type: "submit",
+                            "Sign In"
+                        }
+                    }
+                    p {
+                        style: "margin-top: 1rem; color: #6b7280;",
+                        "Looking for your own draw? Open the reveal link the organizer sent you."
+                    }
+                }
+                if let Some(message) = error() {
+                    p {
+                        style: "margin-top: 1rem; padding: 0.75rem; background: #fee2e2; color: #991b1b; border-radius: 0.375rem;",
+                        "{message}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Drawing, finalizing and handing out reveal links, which keeps pairings hidden unless asked
+#[component]
+fn Dashboard(organizer: String, on_sign_out: EventHandler<()>) -> Element {
     let mut selected_pool = use_signal(|| ExchangePool::IslandLife);
-    let mut exchange_result = use_signal(|| generate_exchange_pairings(selected_pool()));
+    let mut error = use_signal(|| None::<String>);
+    let mut exchange_result = use_signal(|| None::<ExchangeResult>);
+    // Whether `exchange_result` is up to date for the selected pool, so None means it hasn't been drawn
+    let mut loaded = use_signal(|| false);
+    let mut pairings = use_signal(|| None::<Vec<ExchangePairing>>);
+    let mut reveal_links = use_signal(Vec::<RevealLink>::new);
+    let pair_stats = use_resource(move || async move {
+        // Reload after every draw, since draws are saved as they're generated
+        exchange_result.read();
+        server::load_pair_stats(selected_pool()).await
+    });
+
+    // Shows the outcome of loading, drawing or finalizing a draw, hiding the pairings again
+    let mut show = move |result: Result<Option<ExchangeResult>, ServerFnError>| match result {
+        Ok(result) => {
+            exchange_result.set(result);
+            loaded.set(true);
+            pairings.set(None);
+            error.set(None);
+        }
+        Err(e) => error.set(Some(e.to_string())),
+    };
 
-    let regenerate = move |_| {
-        exchange_result.set(generate_exchange_pairings(selected_pool()));
+    use_hook(move || spawn(async move { show(server::load_exchange(selected_pool()).await) }));
+
+    let mut select_pool = move |pool: ExchangePool| {
+        selected_pool.set(pool);
+        exchange_result.set(None);
+        loaded.set(false);
+        reveal_links.set(vec![]);
+        spawn(async move { show(server::load_exchange(pool).await) });
+    };
+
+    let regenerate = move |_| async move { show(server::regenerate_exchange(selected_pool()).await.map(Some)) };
+    let finalize = move |_| async move { show(server::finalize_exchange(selected_pool()).await.map(Some)) };
+    let show_pairings = move |_| async move {
+        match server::show_pairings(selected_pool()).await {
+            Ok(shown) => pairings.set(Some(shown)),
+            Err(e) => error.set(Some(e.to_string())),
+        }
     };
+    let issue_links = move |_| async move {
+        match server::issue_reveal_links(selected_pool()).await {
+            Ok(links) => reveal_links.set(links),
+            Err(e) => error.set(Some(e.to_string())),
+        }
+    };
+    let is_finalized = exchange_result
+        .read()
+        .as_ref()
+        .is_some_and(|result| result.status == ExchangeStatus::Finalized);
 
     rsx! {
         Title {"Christmas Gift Exchange"}
@@ -59,6 +683,15 @@ pub fn app() -> Element {
                     style: "font-size: 2.5rem; font-weight: bold; text-align: center; margin-bottom: 2rem; color: #166534;",
                     "🎄 Christmas Gift Exchange 🎁"
                 }
+                p {
+                    style: "text-align: center; margin: -1rem 0 1.5rem; color: #374151;",
+                    "Signed in as {organizer} · "
+                    button {
+                        style: "background: none; border: none; padding: 0; color: #166534; text-decoration: underline; cursor: pointer; font: inherit;",
+                        onclick: move |_| on_sign_out.call(()),
+                        "Sign out"
+                    }
+                }
 
                 // Pool selector
                 div {
@@ -75,10 +708,7 @@ pub fn app() -> Element {
                             } else {
                                 "padding: 0.75rem 1.5rem; background: #e5e7eb; color: #374151; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;"
                             },
-                            onclick: move |_| {
-                                selected_pool.set(ExchangePool::IslandLife);
-                                exchange_result.set(generate_exchange_pairings(ExchangePool::IslandLife));
-                            },
+                            onclick: move |_| select_pool(ExchangePool::IslandLife),
                             "Island Life"
                         }
                         button {
@@ -87,10 +717,7 @@ pub fn app() -> Element {
                             } else {
                                 "padding: 0.75rem 1.5rem; background: #e5e7eb; color: #374151; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;"
                             },
-                            onclick: move |_| {
-                                selected_pool.set(ExchangePool::Grabergishimazureson);
-                                exchange_result.set(generate_exchange_pairings(ExchangePool::Grabergishimazureson));
-                            },
+                            onclick: move |_| select_pool(ExchangePool::Grabergishimazureson),
                             "Grabergishimazureson"
                         }
                         button {
@@ -99,64 +726,178 @@ pub fn app() -> Element {
                             } else {
                                 "padding: 0.75rem 1.5rem; background: #e5e7eb; color: #374151; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;"
                             },
-                            onclick: move |_| {
-                                selected_pool.set(ExchangePool::Pets);
-                                exchange_result.set(generate_exchange_pairings(ExchangePool::Pets));
-                            },
+                            onclick: move |_| select_pool(ExchangePool::Pets),
                             "Pets"
                         }
                     }
-                    button {
-                        style: "margin-top: 1rem; padding: 0.5rem 1rem; background: #dc2626; color: white; border-radius: 0.375rem; font-weight: 500; display: flex; align-items: center; gap: 0.5rem; border: none; cursor: pointer;",
-                        onclick: regenerate,
-                        "🔄 Regenerate Pairings"
+                    if is_finalized {
+                        p {
+                            style: "margin-top: 1rem; color: #374151; font-weight: 500;",
+                            "🔒 This season's draw is finalized. An admin must unlock it before it can be regenerated."
+                        }
+                    } else if loaded() && exchange_result.read().is_none() {
+                        div {
+                            style: "margin-top: 1rem; display: flex; gap: 1rem; flex-wrap: wrap; align-items: center;",
+                            span {
+                                style: "color: #374151;",
+                                "Nobody has drawn this pool for this season yet."
+                            }
+                            button {
+                                style: "padding: 0.5rem 1rem; background: #16a34a; color: white; border-radius: 0.375rem; font-weight: 500; display: flex; align-items: center; gap: 0.5rem; border: none; cursor: pointer;",
+                                onclick: regenerate,
+                                "🎲 Draw Pairings"
+                            }
+                        }
+                    } else if loaded() {
+                        div {
+                            style: "margin-top: 1rem; display: flex; gap: 1rem; flex-wrap: wrap;",
+                            button {
+                                style: "padding: 0.5rem 1rem; background: #dc2626; color: white; border-radius: 0.375rem; font-weight: 500; display: flex; align-items: center; gap: 0.5rem; border: none; cursor: pointer;",
+                                onclick: regenerate,
+                                "🔄 Regenerate Pairings"
+                            }
+                            button {
+                                style: "padding: 0.5rem 1rem; background: #166534; color: white; border-radius: 0.375rem; font-weight: 500; display: flex; align-items: center; gap: 0.5rem; border: none; cursor: pointer;",
+                                onclick: finalize,
+                                "🔒 Finalize Draw"
+                            }
+                        }
+                    }
+                    if let Some(message) = error() {
+                        p {
+                            style: "margin-top: 1rem; padding: 0.75rem; background: #fee2e2; color: #991b1b; border-radius: 0.375rem;",
+                            "{message}"
+                        }
                     }
                 }
 
-                // Year and Letter Display
-                div {
-                    style: "background: white; border-radius: 0.5rem; box-shadow: 0 10px 15px -3px rgba(0, 0, 0, 0.1); padding: 1.5rem; margin-bottom: 1.5rem;",
+                if let Some(result) = exchange_result() {
+                    // Year and Theme Display
                     div {
-                        style: "text-align: center;",
-                        h3 {
-                            style: "font-size: 1.25rem; font-weight: 600; color: #1f2937; margin-bottom: 0.5rem;",
-                            "Letter for {exchange_result().year}"
-                        }
+                        style: "background: white; border-radius: 0.5rem; box-shadow: 0 10px 15px -3px rgba(0, 0, 0, 0.1); padding: 1.5rem; margin-bottom: 1.5rem;",
                         div {
-                            style: "font-size: 4rem; font-weight: bold; color: #16a34a;",
-                            "{exchange_result().year_letter}"
+                            style: "text-align: center;",
+                            h3 {
+                                style: "font-size: 1.25rem; font-weight: 600; color: #1f2937; margin-bottom: 0.5rem;",
+                                "{result.theme.kind.label()} for {result.year}"
+                            }
+                            div {
+                                style: if result.theme.kind == ThemeKind::Letter {
+                                    "font-size: 4rem; font-weight: bold; color: #16a34a;"
+                                } else {
+                                    "font-size: 2rem; font-weight: bold; color: #16a34a;"
+                                },
+                                "{result.theme}"
+                            }
                         }
                     }
-                }
 
-                // Pairings Display
-                div {
-                    style: "background: white; border-radius: 0.5rem; box-shadow: 0 10px 15px -3px rgba(0, 0, 0, 0.1); padding: 1.5rem;",
-                    h2 {
-                        style: "font-size: 1.5rem; font-weight: 600; margin-bottom: 1rem; color: #1f2937;",
-                        "Gift Exchange Pairings"
-                    }
+                    // Pairings Display
                     div {
-                        style: "display: flex; flex-direction: column; gap: 0.75rem;",
-                        for pairing in &exchange_result().pairings {
+                        style: "background: white; border-radius: 0.5rem; box-shadow: 0 10px 15px -3px rgba(0, 0, 0, 0.1); padding: 1.5rem;",
+                        h2 {
+                            style: "font-size: 1.5rem; font-weight: 600; margin-bottom: 1rem; color: #1f2937;",
+                            "Gift Exchange Pairings"
+                        }
+                        if let Some(pairings) = pairings() {
                             div {
-                                style: "display: flex; align-items: center; padding: 0.75rem; background: #f9fafb; border-radius: 0.5rem;",
-                                div {
-                                    style: "flex: 1; font-size: 1.125rem; font-weight: 500; color: #374151;",
-                                    "{pairing.giver}"
+                                style: "display: flex; flex-direction: column; gap: 0.75rem;",
+                                for pairing in pairings {
+                                    div {
+                                        style: "display: flex; align-items: center; padding: 0.75rem; background: #f9fafb; border-radius: 0.5rem;",
+                                        div {
+                                            style: "flex: 1; font-size: 1.125rem; font-weight: 500; color: #374151;",
+                                            "{pairing.giver}"
+                                        }
+                                        div {
+                                            style: "font-size: 1.5rem; color: #16a34a; margin: 0 1rem;",
+                                            "→"
+                                        }
+                                        div {
+                                            style: "flex: 1; font-size: 1.125rem; font-weight: 500; color: #374151; text-align: right;",
+                                            "{pairing.receiver}"
+                                        }
+                                    }
                                 }
-                                div {
-                                    style: "font-size: 1.5rem; color: #16a34a; margin: 0 1rem;",
-                                    "→"
+                            }
+                        } else {
+                            p {
+                                style: "color: #374151; margin-bottom: 1rem;",
+                                "{result.givers.len()} people are in this draw. Pairings are hidden so nobody's surprise is spoiled; send each person their reveal link instead."
+                            }
+                            div {
+                                style: "display: flex; gap: 1rem; flex-wrap: wrap;",
+                                if !result.blind {
+                                    button {
+                                        style: "padding: 0.5rem 1rem; background: #16a34a; color: white; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;",
+                                        onclick: issue_links,
+                                        "🔗 Create Reveal Links"
+                                    }
+                                    button {
+                                        style: "padding: 0.5rem 1rem; background: #e5e7eb; color: #374151; border-radius: 0.375rem; font-weight: 500; border: none; cursor: pointer;",
+                                        onclick: show_pairings,
+                                        "👀 Show All Pairings"
+                                    }
+                                    a {
+                                        style: "padding: 0.5rem 1rem; background: #e5e7eb; color: #374151; border-radius: 0.375rem; font-weight: 500; text-decoration: none;",
+                                        href: "/slips/{selected_pool().slug()}",
+                                        target: "_blank",
+                                        "🖨️ Printable Slips"
+                                    }
+                                }
+                            }
+                            if result.blind {
+                                p {
+                                    style: "margin-top: 1rem; color: #6b7280;",
+                                    "Blind organizer mode is on: nobody can see the full draw or the reveal links here. Email each person their link with `cargo run -- email {selected_pool().slug()}`, and they learn only their own pairing from it."
                                 }
-                                div {
-                                    style: "flex: 1; font-size: 1.125rem; font-weight: 500; color: #374151; text-align: right;",
-                                    "{pairing.receiver}"
+                            }
+                        }
+                        if !reveal_links().is_empty() {
+                            p {
+                                style: "margin-top: 1rem; color: #6b7280;",
+                                "Links created earlier for this draw no longer work."
+                            }
+                            div {
+                                style: "margin-top: 0.5rem; display: flex; flex-direction: column; gap: 0.5rem;",
+                                for link in reveal_links() {
+                                    div {
+                                        style: "display: flex; gap: 1rem; padding: 0.5rem 0.75rem; background: #f9fafb; border-radius: 0.5rem;",
+                                        span {
+                                            style: "font-weight: 500; color: #374151; min-width: 8rem;",
+                                            "{link.giver}"
+                                        }
+                                        a {
+                                            style: "color: #16a34a; word-break: break-all;",
+                                            href: "/reveal/{link.token}",
+                                            target: "_blank",
+                                            "/reveal/{link.token}"
+                                        }
+                                    }
                                 }
                             }
                         }
                     }
                 }
+
+                // Pair Frequency Heatmap
+                div {
+                    style: "background: white; border-radius: 0.5rem; box-shadow: 0 10px 15px -3px rgba(0, 0, 0, 0.1); padding: 1.5rem; margin-top: 1.5rem;",
+                    h2 {
+                        style: "font-size: 1.5rem; font-weight: 600; margin-bottom: 1rem; color: #1f2937;",
+                        "Pairing History"
+                    }
+                    match &*pair_stats.read() {
+                        Some(Ok(stats)) => rsx! { PairHeatmap { stats: stats.clone() } },
+                        Some(Err(e)) => rsx! {
+                            p {
+                                style: "color: #991b1b;",
+                                "Failed to load pairing history: {e}"
+                            }
+                        },
+                        None => rsx! {},
+                    }
+                }
             }
         }
     }
@@ -1,32 +1,97 @@
+use anyhow::{Context, Result};
+
+use crate::data;
 use crate::giftexchange::ExchangePool;
-use chrono::{Datelike, Local};
+use crate::storage::Storage;
+use crate::themes::{self, GiftTheme, ThemeHistory};
 
-/// Returns a letter identifier for the given exchange pool
+/// Returns the gift theme for the given exchange pool and season
 ///
-/// - IslandLife always returns 'I'
-/// - Pets always returns 'P'
-/// - Grabergishimazureson returns a random letter from a predefined set
-pub fn letter_for_pool(pool: ExchangePool) -> char {
-    let letters = "ACDIJLMNORSTUXYZ".chars().collect::<Vec<char>>();
-
-    match pool {
-        ExchangePool::IslandLife => 'I',
-        ExchangePool::Grabergishimazureson => *fastrand::choice(letters.iter()).unwrap(),
-        ExchangePool::Pets => 'P',
+/// The theme is drawn once per pool per season from the pool's configured
+/// options and persisted, so later calls for the same season return the same
+/// theme. Island Life is always 'I' and Pets always 'P'.
+pub fn theme_for_pool(storage: &mut dyn Storage, pool: ExchangePool, season: i32) -> GiftTheme {
+    let config = data::theme_config_for_pool(pool);
+    let history = storage.load_themes().unwrap_or_else(|e| {
+        eprintln!("Warning: {e:#}. Drawing the theme without history.");
+        ThemeHistory::default()
+    });
+    if let Some(value) = history.theme_for(pool, season) {
+        return GiftTheme {
+            kind: config.kind,
+            value: value.to_string(),
+        };
+    }
+
+    let theme = themes::draw_theme(pool, season, &config, &history);
+    if config.options.len() > 1 {
+        if let Err(e) = storage.save_theme(pool, season, &theme.value) {
+            eprintln!("Warning: failed to save the theme: {e:#}");
+        }
     }
+    theme
 }
 
-/// Returns the current year
-pub fn current_year() -> i32 {
-    Local::now().year()
+/// Returns who is running the program, for recording who changed an exchange
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Replaces each `{name}` in `template` with its value from `values`
+///
+/// `what` names the template in errors, e.g. "email template". Braces around
+/// anything but a name, as in JSON, are left alone. Unknown names are an error
+/// rather than left in, so a typo doesn't go out to the whole family.
+pub fn fill_template(template: &str, what: &str, values: &[(&str, String)]) -> Result<String> {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_lowercase() || c == '_'))
+            .unwrap_or(rest.len());
+        if name_len == 0 || !rest[name_len..].starts_with('}') {
+            filled.push('{');
+            continue;
+        }
+        let name = &rest[..name_len];
+        let (_, value) = values.iter().find(|(key, _)| *key == name).with_context(|| {
+            let known: Vec<String> = values.iter().map(|(key, _)| format!("{{{key}}}")).collect();
+            format!("Unknown placeholder {{{name}}} in the {what}. Use one of: {}", known.join(", "))
+        })?;
+        filled.push_str(value);
+        rest = &rest[name_len + 1..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
 }
 
 /// Parses command line pool argument into ExchangePool enum
 pub fn parse_pool_arg(arg: &str) -> Result<ExchangePool, String> {
-    match arg {
-        "island" => Ok(ExchangePool::IslandLife),
-        "graber" => Ok(ExchangePool::Grabergishimazureson),
-        "pets" => Ok(ExchangePool::Pets),
-        _ => Err(format!("Invalid pool specified: '{arg}'. Valid options are: island, graber, pets")),
+    ExchangePool::from_slug(arg)
+        .ok_or_else(|| format!("Invalid pool specified: '{arg}'. Valid options are: island, graber, pets"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_template_fills_known_names_and_leaves_other_braces() {
+        let values = [("giver", "Claire".to_string()), ("days_left", "3".to_string())];
+        let filled = fill_template(r#"{"text": "{giver}, {days_left} days"} {Giver} { } {"#, "test", &values).unwrap();
+        assert_eq!(filled, r#"{"text": "Claire, 3 days"} {Giver} { } {"#);
+    }
+
+    #[test]
+    fn fill_template_refuses_unknown_names() {
+        let error = fill_template("Hi {reciever}", "email template", &[("receiver", "Duncan".to_string())]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown placeholder {reciever} in the email template. Use one of: {receiver}"
+        );
     }
 }
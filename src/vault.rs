@@ -0,0 +1,302 @@
+use anyhow::{Context, Result};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{audit::to_hex, giftexchange::ExchangePool, history::HistoricalPairing, storage::Storage};
+
+/// Environment variable with the admin key, as 64 hex digits
+pub const ADMIN_KEY_ENV: &str = "CHRISTMAS_ADMIN_KEY";
+
+/// Marks an encrypted value. Receivers saved before pairings were encrypted
+/// are plain names without it.
+const ENCRYPTED_PREFIX: &str = "enc1:";
+
+/// A saved pairing as it's stored, with the receiver encrypted under the admin key
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptedPairing {
+    pub year: i32,
+    pub pool: ExchangePool,
+    pub giver: String,
+    pub receiver: String,
+}
+
+/// A ChaCha20-Poly1305 key
+#[derive(Clone)]
+pub struct VaultKey([u8; 32]);
+
+impl VaultKey {
+    /// The admin key from `CHRISTMAS_ADMIN_KEY`, needed to draw or read pairings
+    pub fn admin() -> Result<Self> {
+        let value = std::env::var(ADMIN_KEY_ENV).with_context(|| {
+            format!("Set {ADMIN_KEY_ENV} to the admin key to work with pairings. Create one with `cargo run -- keygen`.")
+        })?;
+        Self::from_hex(value.trim()).with_context(|| format!("{ADMIN_KEY_ENV} must be 64 hex digits"))
+    }
+
+    /// A new key from the operating system's random number generator
+    pub fn generate() -> Result<Self> {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes).context("Failed to generate a key")?;
+        Ok(VaultKey(bytes))
+    }
+
+    /// The key a reveal token opens
+    ///
+    /// Derived with a prefix so it differs from the token hash kept in storage.
+    pub fn for_token(token: &str) -> Self {
+        VaultKey(Sha256::digest(format!("christmas reveal key:{token}")).into())
+    }
+
+    fn from_hex(value: &str) -> Result<Self> {
+        let bytes = from_hex(value)?;
+        Ok(VaultKey(bytes.try_into().map_err(|_| anyhow::anyhow!("Expected 32 bytes"))?))
+    }
+
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    /// Encrypts `plaintext`, bound to `context` so it can't be moved to another record
+    pub fn encrypt(&self, plaintext: &str, context: &str) -> Result<String> {
+        let mut nonce = [0u8; 12];
+        getrandom::getrandom(&mut nonce).context("Failed to generate a nonce")?;
+        let payload = Payload {
+            msg: plaintext.as_bytes(),
+            aad: context.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher()
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt"))?;
+        Ok(format!("{ENCRYPTED_PREFIX}{}{}", to_hex(&nonce), to_hex(&ciphertext)))
+    }
+
+    /// Decrypts a value from `encrypt` with the same `context`
+    pub fn decrypt(&self, encrypted: &str, context: &str) -> Result<String> {
+        let bytes = encrypted
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .context("The value isn't encrypted")
+            .and_then(from_hex)?;
+        if bytes.len() < 12 {
+            anyhow::bail!("The encrypted value is too short");
+        }
+        let (nonce, ciphertext) = bytes.split_at(12);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: context.as_bytes(),
+        };
+        let plaintext = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt: wrong key, or the stored value was altered"))?;
+        String::from_utf8(plaintext).context("The decrypted value isn't text")
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&self.0))
+    }
+}
+
+fn from_hex(value: &str) -> Result<Vec<u8>> {
+    if !value.len().is_multiple_of(2) || !value.is_ascii() {
+        anyhow::bail!("Invalid hex");
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).context("Invalid hex"))
+        .collect()
+}
+
+/// What a receiver is encrypted against: the exchange and the giver
+pub fn pairing_context(pool: ExchangePool, year: i32, giver: &str) -> String {
+    format!("{}/{year}/{giver}", pool.slug())
+}
+
+/// Whether `receiver` was saved before pairings were encrypted
+pub fn is_plaintext(receiver: &str) -> bool {
+    !receiver.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Encrypts each receiver in `exchange` under `key`
+pub fn encrypt_exchange(
+    key: &VaultKey,
+    pool: ExchangePool,
+    year: i32,
+    exchange: &[(String, String)],
+) -> Result<Vec<EncryptedPairing>> {
+    exchange
+        .iter()
+        .map(|(giver, receiver)| {
+            Ok(EncryptedPairing {
+                year,
+                pool,
+                giver: giver.clone(),
+                receiver: key.encrypt(receiver, &pairing_context(pool, year, giver))?,
+            })
+        })
+        .collect()
+}
+
+/// Decrypts each receiver in `pairings` under `key`
+///
+/// Receivers saved before pairings were encrypted are passed through as they are.
+pub fn decrypt_pairings(key: &VaultKey, pairings: &[EncryptedPairing]) -> Result<Vec<HistoricalPairing>> {
+    pairings
+        .iter()
+        .map(|p| {
+            let receiver = if is_plaintext(&p.receiver) {
+                p.receiver.clone()
+            } else {
+                key.decrypt(&p.receiver, &pairing_context(p.pool, p.year, &p.giver))
+                    .with_context(|| format!("Failed to read who {} gives to in {} {}", p.giver, p.pool, p.year))?
+            };
+            Ok(HistoricalPairing {
+                year: p.year,
+                pool: p.pool,
+                giver: p.giver.clone(),
+                receiver,
+            })
+        })
+        .collect()
+}
+
+/// Loads every saved pairing, decrypted with the admin key
+pub fn load_pairings(storage: &dyn Storage) -> Result<Vec<HistoricalPairing>> {
+    decrypt_pairings(&VaultKey::admin()?, &storage.load_pairings()?)
+}
+
+/// Givers in the saved draw for `pool` in `year`, sorted by name, which can be
+/// listed without the admin key
+pub fn givers(pairings: &[EncryptedPairing], pool: ExchangePool, year: i32) -> Vec<String> {
+    let mut givers: Vec<String> = pairings
+        .iter()
+        .filter(|p| p.pool == pool && p.year == year)
+        .map(|p| p.giver.clone())
+        .collect();
+    givers.sort();
+    givers
+}
+
+/// Encrypts pairings saved before encryption was added, returning how many were encrypted
+pub fn encrypt_plaintext_pairings(storage: &mut dyn Storage, key: &VaultKey) -> Result<usize> {
+    let pairings = storage.load_pairings()?;
+    let mut exchanges: Vec<(ExchangePool, i32)> = vec![];
+    for pairing in pairings.iter().filter(|p| is_plaintext(&p.receiver)) {
+        if !exchanges.contains(&(pairing.pool, pairing.year)) {
+            exchanges.push((pairing.pool, pairing.year));
+        }
+    }
+
+    let mut encrypted = 0;
+    for (pool, year) in exchanges {
+        let saved: Vec<EncryptedPairing> = pairings
+            .iter()
+            .filter(|p| p.pool == pool && p.year == year)
+            .cloned()
+            .collect();
+        encrypted += saved.iter().filter(|p| is_plaintext(&p.receiver)).count();
+        let exchange: Vec<(String, String)> = decrypt_pairings(key, &saved)?
+            .into_iter()
+            .map(|p| (p.giver, p.receiver))
+            .collect();
+        storage.replace_pairings(pool, year, &encrypt_exchange(key, pool, year, &exchange)?)?;
+    }
+    Ok(encrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempPath;
+
+    #[test]
+    fn decrypts_what_it_encrypted() {
+        let key = VaultKey::generate().unwrap();
+        let context = pairing_context(ExchangePool::IslandLife, 2024, "Alec");
+        let encrypted = key.encrypt("Kaylee", &context).unwrap();
+        assert!(!is_plaintext(&encrypted));
+        assert!(!encrypted.contains("Kaylee"));
+        assert_eq!(key.decrypt(&encrypted, &context).unwrap(), "Kaylee");
+    }
+
+    #[test]
+    fn encrypting_twice_uses_fresh_nonces() {
+        let key = VaultKey::generate().unwrap();
+        let first = key.encrypt("Kaylee", "island/2024/Alec").unwrap();
+        assert_ne!(first, key.encrypt("Kaylee", "island/2024/Alec").unwrap());
+    }
+
+    #[test]
+    fn wrong_key_fails() {
+        let context = pairing_context(ExchangePool::IslandLife, 2024, "Alec");
+        let encrypted = VaultKey::generate().unwrap().encrypt("Kaylee", &context).unwrap();
+        assert!(VaultKey::generate().unwrap().decrypt(&encrypted, &context).is_err());
+    }
+
+    #[test]
+    fn swapped_context_fails() {
+        let key = VaultKey::generate().unwrap();
+        let encrypted = key
+            .encrypt("Kaylee", &pairing_context(ExchangePool::IslandLife, 2024, "Alec"))
+            .unwrap();
+        // Moving a receiver to another giver, year or pool must not decrypt
+        for context in [
+            pairing_context(ExchangePool::IslandLife, 2024, "Will"),
+            pairing_context(ExchangePool::IslandLife, 2025, "Alec"),
+            pairing_context(ExchangePool::Grabergishimazureson, 2024, "Alec"),
+        ] {
+            assert!(key.decrypt(&encrypted, &context).is_err());
+        }
+    }
+
+    #[test]
+    fn tampered_ciphertext_fails() {
+        let key = VaultKey::generate().unwrap();
+        let context = pairing_context(ExchangePool::IslandLife, 2024, "Alec");
+        let encrypted = key.encrypt("Kaylee", &context).unwrap();
+        let last = encrypted.chars().last().unwrap();
+        let flipped = if last == '0' { '1' } else { '0' };
+        let tampered = format!("{}{flipped}", &encrypted[..encrypted.len() - 1]);
+        assert!(key.decrypt(&tampered, &context).is_err());
+        assert!(key.decrypt(&encrypted[..encrypted.len() - 2], &context).is_err());
+    }
+
+    #[test]
+    fn token_keys_differ_by_token() {
+        let context = pairing_context(ExchangePool::IslandLife, 2024, "Alec");
+        let encrypted = VaultKey::for_token("abc").encrypt("Kaylee", &context).unwrap();
+        assert_eq!(VaultKey::for_token("abc").decrypt(&encrypted, &context).unwrap(), "Kaylee");
+        assert!(VaultKey::for_token("abd").decrypt(&encrypted, &context).is_err());
+    }
+
+    #[test]
+    fn migrates_plaintext_pairings() {
+        let path = TempPath::new("vault-migration");
+        let mut storage = path.storage();
+        let key = VaultKey::generate().unwrap();
+        let legacy = |giver: &str, receiver: &str| EncryptedPairing {
+            year: 2023,
+            pool: ExchangePool::IslandLife,
+            giver: giver.to_string(),
+            receiver: receiver.to_string(),
+        };
+        storage
+            .save_draw(ExchangePool::IslandLife, 2023, &[], &[legacy("Alec", "Will"), legacy("Will", "Alec")])
+            .unwrap();
+        let exchange = [("Alec".to_string(), "Kaylee".to_string())];
+        let current = encrypt_exchange(&key, ExchangePool::IslandLife, 2024, &exchange).unwrap();
+        storage.save_draw(ExchangePool::IslandLife, 2024, &[], &current).unwrap();
+
+        assert_eq!(encrypt_plaintext_pairings(&mut storage, &key).unwrap(), 2);
+        let saved = storage.load_pairings().unwrap();
+        assert!(saved.iter().all(|p| !is_plaintext(&p.receiver)));
+        let receivers: Vec<_> = decrypt_pairings(&key, &saved).unwrap().into_iter().map(|p| p.receiver).collect();
+        assert_eq!(receivers, ["Will", "Alec", "Kaylee"]);
+
+        // Running it again finds nothing left to encrypt
+        assert_eq!(encrypt_plaintext_pairings(&mut storage, &key).unwrap(), 0);
+    }
+}
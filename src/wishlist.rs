@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+
+use crate::themes::{GiftTheme, ThemeKind};
+
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::Result;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{reveal, storage::Storage, utils};
+
+/// Longest gift idea that can be added, in characters
+pub const MAX_IDEA_LEN: usize = 200;
+
+/// Longest price estimate, in characters
+pub const MAX_PRICE_LEN: usize = 30;
+
+/// Longest link, in characters
+pub const MAX_LINK_LEN: usize = 2000;
+
+/// Most ideas one person can keep on their wishlist
+pub const MAX_WISHLIST_LEN: usize = 50;
+
+/// How much someone would like a gift idea
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    pub fn slug(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+
+    /// The priority named `slug`, if there is one
+    pub fn from_slug(slug: &str) -> Option<Priority> {
+        [Priority::High, Priority::Medium, Priority::Low]
+            .into_iter()
+            .find(|priority| priority.slug() == slug)
+    }
+}
+
+/// One gift idea on someone's wishlist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WishlistItem {
+    pub idea: String,
+    /// Where it can be bought or seen, always an http(s) URL
+    pub link: Option<String>,
+    /// Rough price, written however the family writes budgets, e.g. "$20"
+    pub price: Option<String>,
+    pub priority: Priority,
+    /// Day it was added (YYYY-MM-DD)
+    pub added_on: String,
+}
+
+impl WishlistItem {
+    /// Whether the idea fits a letter theme, going by its first letter
+    pub fn matches_letter(&self, letter: &str) -> bool {
+        let first = |text: &str| text.chars().find(|c| c.is_alphanumeric()).map(|c| c.to_lowercase().to_string());
+        first(&self.idea).is_some_and(|initial| Some(initial) == first(letter))
+    }
+}
+
+/// The wishlists someone with a reveal link can see
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WishlistBoard {
+    /// Who the link's giver gives to
+    pub recipient: String,
+    /// The exchange's theme, whose letter the recipient's ideas can be filtered by
+    pub theme: GiftTheme,
+    /// The recipient's ideas, most wanted first
+    pub recipient_items: Vec<WishlistItem>,
+    /// The giver's own ideas, in the order they were added
+    pub my_items: Vec<WishlistItem>,
+}
+
+impl WishlistBoard {
+    /// The letter this year's gifts start with, if the theme is a letter
+    pub fn letter(&self) -> Option<&str> {
+        (self.theme.kind == ThemeKind::Letter).then_some(self.theme.value.as_str())
+    }
+
+    /// The recipient's ideas, only those fitting the theme's letter if `by_letter`
+    pub fn recipient_ideas(&self, by_letter: bool) -> Vec<&WishlistItem> {
+        self.recipient_items
+            .iter()
+            .filter(|item| match self.letter() {
+                Some(letter) if by_letter => item.matches_letter(letter),
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+/// What's needed to add an idea, as entered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewWish {
+    pub idea: String,
+    pub link: String,
+    pub price: String,
+    pub priority: Priority,
+}
+
+/// The wishlists for the giver whose reveal link is `token`
+///
+/// A giver sees their recipient's list and their own; nobody else's. Lists
+/// belong to the person, not the link's exchange, so they're the same in
+/// every pool and season, and a Santa sees ideas added for earlier years too.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn board(storage: &mut dyn Storage, token: &str) -> Result<WishlistBoard> {
+    let link = reveal::open(storage, token)?;
+    let mut recipient_items = storage.load_wishlist(&link.receiver)?;
+    // Stable, so equally wanted ideas stay in the order they were added
+    recipient_items.sort_by_key(|item| item.priority);
+    Ok(WishlistBoard {
+        theme: utils::theme_for_pool(storage, link.pool, link.year),
+        my_items: storage.load_wishlist(&link.giver)?,
+        recipient_items,
+        recipient: link.receiver,
+    })
+}
+
+/// Adds `wish` to the wishlist of the giver whose reveal link is `token`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn add_wish(storage: &mut dyn Storage, token: &str, wish: &NewWish) -> Result<WishlistBoard> {
+    let owner = reveal::open(storage, token)?.giver;
+    let idea = wish.idea.trim();
+    if idea.is_empty() {
+        anyhow::bail!("The gift idea is empty.");
+    }
+    if idea.chars().count() > MAX_IDEA_LEN {
+        anyhow::bail!("Gift ideas can be at most {MAX_IDEA_LEN} characters.");
+    }
+    let link = parse_link(&wish.link)?;
+    let price = wish.price.trim();
+    if price.chars().count() > MAX_PRICE_LEN {
+        anyhow::bail!("Price estimates can be at most {MAX_PRICE_LEN} characters.");
+    }
+
+    let mut items = storage.load_wishlist(&owner)?;
+    if items.len() >= MAX_WISHLIST_LEN {
+        anyhow::bail!("Wishlists can have at most {MAX_WISHLIST_LEN} ideas. Remove one first.");
+    }
+    items.push(WishlistItem {
+        idea: idea.to_string(),
+        link,
+        price: (!price.is_empty()).then(|| price.to_string()),
+        priority: wish.priority,
+        added_on: chrono::Local::now().format("%Y-%m-%d").to_string(),
+    });
+    storage.save_wishlist(&owner, &items)?;
+    board(storage, token)
+}
+
+/// `link` as entered, if there is one, refusing anything but an http(s) URL
+///
+/// Anything else, such as a `javascript:` URL, would run when the Santa clicks it.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_link(link: &str) -> Result<Option<String>> {
+    let link = link.trim();
+    if link.is_empty() {
+        return Ok(None);
+    }
+    let rest = link.strip_prefix("https://").or_else(|| link.strip_prefix("http://"));
+    if rest.is_none_or(|rest| rest.is_empty()) {
+        anyhow::bail!("Links must start with https:// or http://.");
+    }
+    if link.chars().count() > MAX_LINK_LEN {
+        anyhow::bail!("Links can be at most {MAX_LINK_LEN} characters.");
+    }
+    Ok(Some(link.to_string()))
+}
+
+/// Removes the idea at `index` in the wishlist of the giver whose reveal link is `token`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove_wish(storage: &mut dyn Storage, token: &str, index: usize) -> Result<WishlistBoard> {
+    let link = reveal::open(storage, token)?;
+    let mut items = storage.load_wishlist(&link.giver)?;
+    if index >= items.len() {
+        anyhow::bail!("There's no idea {} on your wishlist.", index + 1);
+    }
+    items.remove(index);
+    storage.save_wishlist(&link.giver, &items)?;
+    board(storage, token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idea(idea: &str) -> WishlistItem {
+        WishlistItem {
+            idea: idea.to_string(),
+            link: None,
+            price: None,
+            priority: Priority::Medium,
+            added_on: "2025-11-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_http_links_are_kept() {
+        assert_eq!(parse_link("  ").unwrap(), None);
+        assert_eq!(
+            parse_link(" https://example.com/skates ").unwrap().as_deref(),
+            Some("https://example.com/skates")
+        );
+        assert_eq!(parse_link("http://example.com").unwrap().as_deref(), Some("http://example.com"));
+        let refused = ["javascript:alert(1)", "JavaScript:alert(1)", "data:text/html,hi", "example.com", "http://", "ftp://x"];
+        for link in refused {
+            assert!(parse_link(link).is_err(), "{link} was accepted");
+        }
+        let long = format!("https://example.com/{}", "a".repeat(MAX_LINK_LEN));
+        assert!(parse_link(&long).is_err());
+        assert!(parse_link(&long[..MAX_LINK_LEN]).is_ok());
+    }
+
+    #[test]
+    fn ideas_match_a_letter_by_their_first_letter() {
+        assert!(idea("Ice skates").matches_letter("I"));
+        assert!(idea("ice skates").matches_letter("i"));
+        assert!(idea("\"Interesting\" book").matches_letter("I"));
+        assert!(idea("Émile's cookbook").matches_letter("é"));
+        assert!(!idea("A nice scarf").matches_letter("I"));
+        assert!(!idea("...").matches_letter("I"));
+        assert!(!idea("Ice skates").matches_letter(""));
+    }
+
+    #[test]
+    fn recipient_ideas_filter_only_by_a_letter_theme() {
+        let board = |kind| WishlistBoard {
+            recipient: "Will".to_string(),
+            theme: GiftTheme {
+                kind,
+                value: "S".to_string(),
+            },
+            recipient_items: vec![idea("Socks"), idea("Book"), idea("scarf")],
+            my_items: vec![],
+        };
+        let ideas = |board: &WishlistBoard, by_letter| -> Vec<String> {
+            board.recipient_ideas(by_letter).into_iter().map(|item| item.idea.clone()).collect()
+        };
+        assert_eq!(ideas(&board(ThemeKind::Letter), true), ["Socks", "scarf"]);
+        assert_eq!(ideas(&board(ThemeKind::Letter), false), ["Socks", "Book", "scarf"]);
+        assert_eq!(ideas(&board(ThemeKind::Idea), true), ["Socks", "Book", "scarf"]);
+    }
+}